@@ -0,0 +1,174 @@
+//! External (out-of-process) reader
+//!
+//! Opens the game process, enumerates its modules, resolves interfaces and
+//! lists entities without injecting anything. Scanning and PE parsing are the same `shared`
+//! code the injected `tf` module runs, read through `ProcessMemory` instead.
+//!
+//! # Usage
+//!
+//! ```text
+//! external [process]                                 read live process
+//! external snapshot <path> [process] [start:size...] capture snapshot to path
+//! external replay <path>                             read captured snapshot
+//! ```
+//!
+//! Snapshots always cover every loaded module, `start:size` pairs (hex) add
+//! heap regions on top. Only replaying works off Windows.
+
+mod reader;
+use reader::Reader;
+#[cfg(windows)]
+use shared::{
+    error::Error,
+    memory::{module::Module, snapshot::Snapshot, source::ProcessMemory},
+    process::find_process_id,
+};
+use shared::{
+    memory::{generic_address::GenericAddress, snapshot::SnapshotMemory, source::MemorySource},
+    GenericErrOr,
+};
+
+/// Game executable we attach to when none is given
+#[cfg(windows)]
+const DEFAULT_PROCESS: &str = "hl2.exe";
+
+/// Modules whose interfaces we list
+const GAME_MODULES: [&str; 3] = ["client.dll", "engine.dll", "vguimatsurface.dll"];
+
+/// Describe where `address` lies, module-relative if possible
+fn describe<S: MemorySource + Clone>(reader: &Reader<S>, address: GenericAddress) -> String {
+    let address = address.exposed_addr();
+
+    match reader.find_containing(address) {
+        Some(info) => format!(
+            "{:#x} ({}+{:#x})",
+            address,
+            info.get_base_name(),
+            address - info.get_base()
+        ),
+        None => format!("{:#x}", address),
+    }
+}
+
+/// Parse `start:size` pair, both hexadecimal with optional `0x`
+#[cfg(windows)]
+fn parse_range(range: &str) -> GenericErrOr<(usize, usize)> {
+    let parse = |x: &str| usize::from_str_radix(x.trim_start_matches("0x"), 16);
+    let (start, size) = range.split_once(':').ok_or(Error::CantFind)?;
+
+    Ok((parse(start)?, parse(size)?))
+}
+
+/// Attach to process named `process_name`, which has to be as wide as we are
+/// (32-bit TF2 needs a 32-bit build)
+#[cfg(windows)]
+fn attach(process_name: &str) -> GenericErrOr<Reader<ProcessMemory>> {
+    let memory = ProcessMemory::open(find_process_id(process_name)?)?;
+    let modules = Module::enumerate_from(&memory, memory.get_peb()?)?;
+
+    Ok(Reader::new(memory, modules))
+}
+
+/// Print modules, interfaces, context objects and entities `reader` sees
+fn dump<S: MemorySource + Clone>(reader: &Reader<S>) -> GenericErrOr<()> {
+    println!("Modules");
+    for info in reader.get_modules() {
+        println!(
+            "  {:#010x} {:#010x} {}",
+            info.get_base(),
+            info.get_size(),
+            info.get_full_path()
+        );
+    }
+
+    for name in GAME_MODULES {
+        println!("Interfaces in {}", name);
+        for interface in reader.get_interfaces(name)? {
+            match interface.get_instance() {
+                Some(instance) => println!(
+                    "  {} -> {}",
+                    interface.get_name(),
+                    describe(reader, instance)
+                ),
+                None => println!("  {} (instantiated per call)", interface.get_name()),
+            }
+        }
+    }
+
+    println!("Context objects");
+    println!("  CCvar -> {}", describe(reader, reader.get_cvar()?));
+    println!(
+        "  CHLClient -> {}",
+        describe(reader, reader.get_chl_client()?)
+    );
+    println!(
+        "  MatSystemSurface -> {}",
+        describe(reader, reader.get_mat_system_surface()?)
+    );
+
+    println!("Entities");
+    for entity in reader.get_entities()? {
+        println!(
+            "  {:4} {} {}",
+            entity.get_index(),
+            describe(reader, entity.get_address()),
+            entity.get_class_name().unwrap_or("(not networked)")
+        );
+    }
+
+    Ok(())
+}
+
+/// Usage line, attaching to live processes only works on Windows
+#[cfg(windows)]
+const USAGE: &str =
+    "Usage: external [process] | snapshot <path> [process] [start:size...] | replay <path>";
+#[cfg(not(windows))]
+const USAGE: &str = "Usage: external replay <path>";
+
+fn main() -> GenericErrOr<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    match args.as_slice() {
+        #[cfg(windows)]
+        ["snapshot", path, rest @ ..] => {
+            let (process_name, ranges) = match rest {
+                [process_name, ranges @ ..] if !process_name.contains(':') => {
+                    (*process_name, ranges)
+                }
+                ranges => (DEFAULT_PROCESS, ranges),
+            };
+
+            let extra = ranges
+                .iter()
+                .map(|range| parse_range(range))
+                .collect::<GenericErrOr<Vec<_>>>()?;
+
+            let reader = attach(process_name)?;
+            let snapshot =
+                Snapshot::capture(reader.get_source(), reader.get_modules().to_vec(), &extra)?;
+            snapshot.save(path)?;
+
+            println!(
+                "Captured {} modules, {} regions to {}",
+                snapshot.get_modules().len(),
+                snapshot.get_regions().len(),
+                path
+            );
+        }
+        ["replay", path] => {
+            let memory = SnapshotMemory::load(path)?;
+            let modules = memory.get_snapshot().get_modules().to_vec();
+
+            dump(&Reader::new(memory, modules))?;
+        }
+        #[cfg(windows)]
+        [] => dump(&attach(DEFAULT_PROCESS)?)?,
+        #[cfg(windows)]
+        [process_name] => dump(&attach(process_name)?)?,
+        _ => eprintln!("{}", USAGE),
+    }
+
+    Ok(())
+}
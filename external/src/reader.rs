@@ -0,0 +1,376 @@
+//! Reader module
+//!
+//! Everything here is generic over the memory source, so it runs the same
+//! against a live process and against recorded memory.
+
+use game::{
+    entities::{self, Entity},
+    interfaces::{self, Interface},
+    signatures,
+};
+use shared::{
+    error::Error,
+    memory::{
+        generic_address::GenericAddress,
+        module::{Module, ModuleInfo},
+        source::MemorySource,
+    },
+    GenericErrOr,
+};
+
+/// Type for reader errors
+pub type ReaderErrOr<T> = GenericErrOr<T>;
+
+/// Game reader over `source`, whose loaded modules are `modules`
+pub struct Reader<S: MemorySource + Clone> {
+    source: S,
+    modules: Vec<ModuleInfo>,
+}
+
+impl<S: MemorySource + Clone> Reader<S> {
+    pub fn new(source: S, modules: Vec<ModuleInfo>) -> Self {
+        Self { source, modules }
+    }
+
+    /// Get memory source we read through
+    // Only capturing snapshots needs it, which attaches, so Windows only
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub fn get_source(&self) -> &S {
+        &self.source
+    }
+
+    /// Get loaded modules
+    pub fn get_modules(&self) -> &[ModuleInfo] {
+        &self.modules
+    }
+
+    /// Get module named `name`, by base name or full path
+    pub fn get_module(&self, name: &str) -> ReaderErrOr<Module<S>> {
+        self.modules
+            .iter()
+            .find(|info| info.matches(name))
+            .map(|info| info.to_module_with(self.source.clone()))
+            .ok_or_else(|| Error::CantFind.into())
+    }
+
+    /// Get every interface module named `name` registered
+    pub fn get_interfaces(&self, name: &str) -> ReaderErrOr<Vec<Interface>> {
+        interfaces::get_interfaces(&self.get_module(name)?)
+    }
+
+    /// Get CCvar instance, same as the injected context resolves it
+    pub fn get_cvar(&self) -> ReaderErrOr<GenericAddress> {
+        let engine = self.get_module("engine.dll")?;
+
+        engine
+            .get_exports()
+            .get("cvar")
+            .ok_or(Error::CantFind)?
+            .path(&[0])
+            .resolve_from(&self.source)
+    }
+
+    /// Get CHLClient instance, same as the injected context resolves it
+    pub fn get_chl_client(&self) -> ReaderErrOr<GenericAddress> {
+        self.get_module("engine.dll")?
+            .find_pattern(signatures::CHL_CLIENT)?
+            .offset(2)?
+            .path(&[0, 0])
+            .resolve_from(&self.source)
+    }
+
+    /// Get MatSystemSurface instance, same as the injected context resolves it
+    pub fn get_mat_system_surface(&self) -> ReaderErrOr<GenericAddress> {
+        self.get_module("vguimatsurface.dll")?
+            .find_pattern(signatures::MAT_SYSTEM_SURFACE)?
+            .offset(1)?
+            .path(&[0, 0])
+            .resolve_from(&self.source)
+    }
+
+    /// Get every entity in the client entity list
+    pub fn get_entities(&self) -> ReaderErrOr<Vec<Entity>> {
+        entities::get_entities(&self.get_module("client.dll")?)
+    }
+
+    /// Get module `address` lies in, if any
+    pub fn find_containing(&self, address: usize) -> Option<&ModuleInfo> {
+        self.modules.iter().find(|info| info.contains(address))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::entities::NUM_ENT_ENTRIES;
+    use shared::memory::{
+        snapshot::{Snapshot, SnapshotMemory},
+        source::{BufferMemory, Protection},
+    };
+    use std::mem::size_of;
+
+    const BASE: usize = 0x10000;
+    const MODULE_SIZE: usize = 0x1000;
+    const ENGINE: usize = BASE;
+    const CLIENT: usize = BASE + MODULE_SIZE;
+    const HEAP: usize = BASE + 2 * MODULE_SIZE;
+    const HEAP_SIZE: usize = 0x30000;
+
+    /// Where things live in both modules, relative to their base
+    const CREATE_INTERFACE: usize = 0x200;
+    const CREATE_FN: usize = 0x240;
+    const GET_CLIENT_CLASS: usize = 0x280;
+    const EXPORTS: usize = 0x400;
+    const INTERFACE_REGS: usize = 0x600;
+    const INTERFACE_NAME: usize = 0x680;
+    const GLOBAL: usize = 0x700;
+    const CLASS_NAME: usize = 0x780;
+
+    /// Heap objects
+    const CVAR: usize = HEAP;
+    const PLAYER: usize = HEAP + 0x100;
+    const WORLD: usize = HEAP + 0x180;
+    const PLAYER_VFTABLE: usize = HEAP + 0x200;
+    const ENTITY_LIST: usize = HEAP + 0x1000;
+
+    /// `CEntInfo` is four pointers wide, with padding on x64
+    const ENT_INFO_SIZE: usize = 4 * size_of::<usize>();
+    const SLOTS: usize = ENTITY_LIST + size_of::<usize>();
+    const ENTITY_LIST_INSTANCE: usize =
+        SLOTS + NUM_ENT_ENTRIES * ENT_INFO_SIZE + 4 * size_of::<usize>();
+
+    struct Process {
+        bytes: Vec<u8>,
+    }
+
+    impl Process {
+        fn put(&mut self, address: usize, value: &[u8]) {
+            let offset = address - BASE;
+            self.bytes[offset..offset + value.len()].copy_from_slice(value);
+        }
+
+        fn put_usize(&mut self, address: usize, value: usize) {
+            self.put(address, &value.to_le_bytes());
+        }
+
+        /// Put code at `address` followed by `ret`, pointers in it being
+        /// RIP-relative on x64
+        fn put_code(&mut self, address: usize, opcode: &[u8], global: usize) {
+            let code = if cfg!(target_pointer_width = "64") {
+                let length = 1 + opcode.len() + 4;
+                let displacement = (global as isize - (address + length) as isize) as i32;
+                [&[0x48][..], opcode, &displacement.to_le_bytes(), &[0xC3]].concat()
+            } else {
+                [opcode, &(global as u32).to_le_bytes()[..], &[0xC3]].concat()
+            };
+
+            self.put(address, &code);
+        }
+
+        /// Put `lea rax, [rip + global]; ret` at `address`, or `mov eax, offset
+        /// global; ret` on x86, which has its own opcode without a ModRM byte
+        fn put_address_of(&mut self, address: usize, global: usize) {
+            if cfg!(target_pointer_width = "64") {
+                self.put_code(address, &[0x8D, 0x05], global);
+            } else {
+                self.put_code(address, &[0xB8], global);
+            }
+        }
+
+        /// Put module as wide as we are at `base`, exporting `exports`, and
+        /// registering `interface` as a global at `instance`
+        fn put_module(
+            &mut self,
+            base: usize,
+            exports: &[(&str, usize)],
+            interface: &str,
+            instance: usize,
+        ) {
+            let (machine, magic, data_directory): (u16, u16, usize) = if size_of::<usize>() == 8 {
+                (0x8664, 0x20B, 0x70)
+            } else {
+                (0x14C, 0x10B, 0x60)
+            };
+
+            self.put(base, b"MZ");
+            self.put(base + 0x3C, &0x80u32.to_le_bytes());
+            self.put(base + 0x80, b"PE\0\0");
+            self.put(base + 0x84, &machine.to_le_bytes());
+            self.put(base + 0x98, &magic.to_le_bytes());
+            self.put(base + 0x98 + data_directory - 4, &16u32.to_le_bytes());
+            self.put(
+                base + 0x98 + data_directory,
+                &(EXPORTS as u32).to_le_bytes(),
+            );
+
+            // Export directory, then its function, name and ordinal tables, then names
+            let count = (exports.len() as u32).to_le_bytes();
+            self.put(base + EXPORTS + 0x14, &count);
+            self.put(base + EXPORTS + 0x18, &count);
+            self.put(
+                base + EXPORTS + 0x1C,
+                &(EXPORTS as u32 + 0x40).to_le_bytes(),
+            );
+            self.put(
+                base + EXPORTS + 0x20,
+                &(EXPORTS as u32 + 0x60).to_le_bytes(),
+            );
+            self.put(
+                base + EXPORTS + 0x24,
+                &(EXPORTS as u32 + 0x80).to_le_bytes(),
+            );
+            for (i, &(name, rva)) in exports.iter().enumerate() {
+                let name_rva = EXPORTS + 0xA0 + i * 0x20;
+                self.put(base + EXPORTS + 0x40 + i * 4, &(rva as u32).to_le_bytes());
+                self.put(
+                    base + EXPORTS + 0x60 + i * 4,
+                    &(name_rva as u32).to_le_bytes(),
+                );
+                self.put(base + EXPORTS + 0x80 + i * 2, &(i as u16).to_le_bytes());
+                self.put(base + name_rva, &[name.as_bytes(), b"\0"].concat());
+            }
+
+            // `CreateInterface` loads `s_pInterfaceRegs`, its one entry returns `instance`
+            self.put_code(
+                base + CREATE_INTERFACE,
+                &[0x8B, 0x0D],
+                base + INTERFACE_REGS,
+            );
+            self.put_address_of(base + CREATE_FN, instance);
+            self.put_usize(base + INTERFACE_REGS, base + INTERFACE_REGS + 0x10);
+            self.put_usize(base + INTERFACE_REGS + 0x10, base + CREATE_FN);
+            self.put_usize(
+                base + INTERFACE_REGS + 0x10 + size_of::<usize>(),
+                base + INTERFACE_NAME,
+            );
+            self.put(
+                base + INTERFACE_NAME,
+                &[interface.as_bytes(), b"\0"].concat(),
+            );
+        }
+
+        /// Put entity `entity` at `index` of the entity list
+        fn put_entity(&mut self, index: usize, entity: usize, serial: i32) {
+            let slot = SLOTS + index * ENT_INFO_SIZE;
+            self.put_usize(slot, entity);
+            self.put(slot + size_of::<usize>(), &serial.to_le_bytes());
+        }
+    }
+
+    /// Process with engine.dll, client.dll, and a heap holding `CCvar`, the
+    /// entity list, a networked player at index 1 and the world at index 3
+    fn process() -> Process {
+        let mut process = Process {
+            bytes: vec![0; 2 * MODULE_SIZE + HEAP_SIZE],
+        };
+
+        process.put_module(
+            ENGINE,
+            &[("CreateInterface", CREATE_INTERFACE), ("cvar", GLOBAL)],
+            "VEngineCvar004",
+            CVAR,
+        );
+        process.put_usize(ENGINE + GLOBAL, CVAR);
+
+        process.put_module(
+            CLIENT,
+            &[("CreateInterface", CREATE_INTERFACE)],
+            "VClientEntityList003",
+            ENTITY_LIST_INSTANCE,
+        );
+
+        // `GetClientClass` returns the player's `ClientClass`, in client.dll
+        process.put_address_of(CLIENT + GET_CLIENT_CLASS, CLIENT + GLOBAL);
+        process.put_usize(
+            CLIENT + GLOBAL + 2 * size_of::<usize>(),
+            CLIENT + CLASS_NAME,
+        );
+        process.put(
+            CLIENT + GLOBAL + 5 * size_of::<usize>(),
+            &247i32.to_le_bytes(),
+        );
+        process.put(CLIENT + CLASS_NAME, b"CTFPlayer\0");
+
+        // Player's `IClientNetworkable` vptr follows two others, world has none
+        process.put_usize(PLAYER + 2 * size_of::<usize>(), PLAYER_VFTABLE);
+        process.put_usize(
+            PLAYER_VFTABLE + 2 * size_of::<usize>(),
+            CLIENT + GET_CLIENT_CLASS,
+        );
+        process.put_entity(1, PLAYER, 5);
+        process.put_entity(3, WORLD, 2);
+
+        process
+    }
+
+    /// Reader over a snapshot of `process`
+    fn reader(process: Process) -> Reader<SnapshotMemory> {
+        let memory = BufferMemory::new(BASE, process.bytes, Protection::READ_WRITE_EXECUTE);
+        let modules = vec![
+            ModuleInfo::new(ENGINE, MODULE_SIZE, String::new(), "engine.dll".to_owned()),
+            ModuleInfo::new(CLIENT, MODULE_SIZE, String::new(), "client.dll".to_owned()),
+        ];
+
+        let snapshot = Snapshot::capture(&memory, modules, &[(HEAP, HEAP_SIZE)]).unwrap();
+        let modules = snapshot.get_modules().to_vec();
+
+        Reader::new(SnapshotMemory::try_from(snapshot).unwrap(), modules)
+    }
+
+    #[test]
+    fn cvar() {
+        let reader = reader(process());
+
+        assert_eq!(reader.get_cvar().unwrap().exposed_addr(), CVAR);
+        assert_eq!(
+            reader
+                .find_containing(ENGINE + GLOBAL)
+                .unwrap()
+                .get_base_name(),
+            "engine.dll"
+        );
+        assert!(reader.find_containing(CVAR).is_none());
+    }
+
+    #[test]
+    fn interfaces() {
+        let reader = reader(process());
+
+        let interfaces = reader.get_interfaces("ENGINE.DLL").unwrap();
+        assert_eq!(interfaces.len(), 1);
+        assert_eq!(interfaces[0].get_name(), "VEngineCvar004");
+        assert_eq!(
+            interfaces[0].get_instance().map(|x| x.exposed_addr()),
+            Some(CVAR)
+        );
+
+        assert!(reader.get_interfaces("server.dll").is_err());
+    }
+
+    #[test]
+    fn entities() {
+        let entities = reader(process()).get_entities().unwrap();
+        assert_eq!(entities.len(), 2);
+
+        let player = &entities[0];
+        assert_eq!(player.get_index(), 1);
+        assert_eq!(player.get_serial(), 5);
+        assert_eq!(player.get_address().exposed_addr(), PLAYER);
+        assert_eq!(player.get_class_name(), Some("CTFPlayer"));
+        assert_eq!(player.get_class_id(), Some(247));
+
+        let world = &entities[1];
+        assert_eq!(world.get_index(), 3);
+        assert_eq!(world.get_class_name(), None);
+        assert_eq!(world.get_class_id(), None);
+    }
+
+    #[test]
+    fn corrupted_entity_list() {
+        // Link out of the slots, we got the layout wrong
+        let mut process = process();
+        process.put_usize(SLOTS + ENT_INFO_SIZE + 2 * size_of::<usize>(), CVAR);
+
+        assert!(reader(process).get_entities().is_err());
+    }
+}
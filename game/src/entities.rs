@@ -0,0 +1,173 @@
+//! Entities module
+//!
+//! Walks the client entity list and names each entity's class. Like
+//! interfaces, everything is read and decoded rather than called, so it works
+//! on any memory source.
+
+use crate::interfaces::{self, get_global_instance};
+use shared::{
+    error::Error,
+    memory::{generic_address::GenericAddress, module::Module, source::MemorySource},
+    GenericErrOr,
+};
+use std::mem::size_of;
+
+/// Type for entities errors
+pub type EntitiesErrOr<T> = GenericErrOr<T>;
+
+/// Slots in the entity list, networked and not
+pub const NUM_ENT_ENTRIES: usize = 4096;
+
+/// Upper bound on class name length
+const MAX_NAME_LENGTH: usize = 128;
+
+/// Index of `GetClientClass` in `IClientNetworkable`'s vftable
+const GET_CLIENT_CLASS_INDEX: usize = 2;
+
+/// Offset of the `IClientNetworkable` vptr in entities, after `IClientUnknown`
+/// and `IClientRenderable`'s
+const NETWORKABLE_OFFSET: usize = 2 * size_of::<usize>();
+
+/// Game CEntInfo class, one slot of the entity list
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct CEntInfo {
+    entity: usize,
+    serial: i32,
+    prev: usize,
+    next: usize,
+}
+
+/// Game ClientClass class, networked classes are a singly linked list of these
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct ClientClass {
+    create_fn: usize,
+    create_event_fn: usize,
+    network_name: usize,
+    recv_table: usize,
+    next: usize,
+    class_id: i32,
+}
+
+/// Entity in the client entity list
+#[derive(Debug, Clone)]
+pub struct Entity {
+    index: usize,
+    serial: i32,
+    address: GenericAddress,
+    class_name: Option<String>,
+    class_id: Option<i32>,
+}
+
+impl Entity {
+    /// Get index in the entity list
+    pub fn get_index(&self) -> usize {
+        self.index
+    }
+
+    /// Get serial number, bumped whenever the slot is reused
+    pub fn get_serial(&self) -> i32 {
+        self.serial
+    }
+
+    /// Get entity address
+    pub fn get_address(&self) -> GenericAddress {
+        self.address
+    }
+
+    /// Get network class name (e.g. `CTFPlayer`), if the entity is networked
+    pub fn get_class_name(&self) -> Option<&str> {
+        self.class_name.as_deref()
+    }
+
+    /// Get network class id, if the entity is networked
+    pub fn get_class_id(&self) -> Option<i32> {
+        self.class_id
+    }
+}
+
+/// Get address of the entity list's slots
+///
+/// `CClientEntityList` derives from `CBaseEntityList` first, then from the
+/// `IClientEntityList` the interface hands out. The slots follow
+/// `CBaseEntityList`'s vptr, and two `CEntInfoList`s follow them.
+fn get_entity_slots<S: MemorySource + Clone>(client: &Module<S>) -> EntitiesErrOr<usize> {
+    let instance = interfaces::find_interface(client, "VClientEntityList")?
+        .get_instance()
+        .ok_or(Error::CantFind)?
+        .exposed_addr();
+
+    let base_entity_list_size =
+        size_of::<usize>() + NUM_ENT_ENTRIES * size_of::<CEntInfo>() + 4 * size_of::<usize>();
+
+    Ok(instance
+        .checked_sub(base_entity_list_size)
+        .ok_or(Error::Overflow)?
+        + size_of::<usize>())
+}
+
+/// Get class `entity` was networked as, if it's networked, decoding its
+/// `GetClientClass`, which just returns a global
+fn get_client_class<S: MemorySource + Clone>(
+    client: &Module<S>,
+    entity: usize,
+) -> Option<ClientClass> {
+    let source = client.get_source();
+
+    let vftable = source.read::<usize>(entity + NETWORKABLE_OFFSET).ok()?;
+    let function = source
+        .read::<usize>(vftable + GET_CLIENT_CLASS_INDEX * size_of::<usize>())
+        .ok()?;
+
+    // Pointers and integers, only padded on x64
+    unsafe { source.read_unchecked::<ClientClass>(get_global_instance(client, function)?) }.ok()
+}
+
+/// Get every entity in the list of `client` (client.dll)
+///
+/// # Example
+///
+/// ```ignore
+/// for entity in get_entities(&Module::new("client.dll")?)? {
+///     println!("{} {:?}", entity.get_index(), entity.get_class_name());
+/// }
+/// ```
+pub fn get_entities<S: MemorySource + Clone>(client: &Module<S>) -> EntitiesErrOr<Vec<Entity>> {
+    let source = client.get_source();
+    let slots = get_entity_slots(client)?;
+    let slots_end = slots + NUM_ENT_ENTRIES * size_of::<CEntInfo>();
+
+    let mut result = Vec::new();
+    for index in 0..NUM_ENT_ENTRIES {
+        let info =
+            unsafe { source.read_unchecked::<CEntInfo>(slots + index * size_of::<CEntInfo>())? };
+
+        // Links stay within the slots, anything else means we got the layout wrong
+        if [info.prev, info.next]
+            .iter()
+            .any(|&link| link != 0 && !(slots..slots_end).contains(&link))
+        {
+            return Err(Error::CantFind.into());
+        }
+
+        if info.entity == 0 {
+            continue;
+        }
+
+        let class = get_client_class(client, info.entity);
+        result.push(Entity {
+            index,
+            serial: info.serial,
+            address: GenericAddress::from(info.entity),
+            class_name: class.and_then(|class| {
+                source
+                    .read_c_string(class.network_name, MAX_NAME_LENGTH)
+                    .ok()
+            }),
+            class_id: class.map(|class| class.class_id),
+        });
+    }
+
+    Ok(result)
+}
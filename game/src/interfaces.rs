@@ -0,0 +1,404 @@
+//! Interfaces module
+//!
+//! Walks the interface registry every Source module keeps behind its
+//! `CreateInterface` export. Everything is resolved statically, by decoding
+//! code rather than calling it, so it works on any memory source, in or out
+//! of the game's process.
+
+use shared::{
+    error::Error,
+    memory::{
+        generic_address::GenericAddress,
+        module::Module,
+        pod::Pod,
+        source::MemorySource,
+        x86::{self, Instruction, Mode, OpcodeMap, MAX_INSTRUCTION_LENGTH},
+    },
+    GenericErrOr,
+};
+
+/// Type for interfaces errors
+pub type InterfacesErrOr<T> = GenericErrOr<T>;
+
+/// How many instructions we decode looking for something before giving up
+const MAX_INSTRUCTIONS: usize = 32;
+
+/// How many jump thunks we follow from `CreateInterface`
+const MAX_THUNKS: usize = 2;
+
+/// Upper bound on registry entries, guards against corrupted lists
+const MAX_INTERFACES: usize = 256;
+
+/// Upper bound on interface name length
+const MAX_NAME_LENGTH: usize = 128;
+
+/// Game InterfaceReg class, registry is a singly linked list of these
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct InterfaceReg {
+    create_fn: usize,
+    name: usize,
+    next: usize,
+}
+
+unsafe impl Pod for InterfaceReg {}
+
+/// Interface registered by a module
+#[derive(Debug, Clone)]
+pub struct Interface {
+    name: String,
+    create_fn: GenericAddress,
+    instance: Option<GenericAddress>,
+}
+
+impl Interface {
+    /// Get versioned interface name (e.g. `VEngineCvar004`)
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get function that creates the interface
+    pub fn get_create_fn(&self) -> GenericAddress {
+        self.create_fn
+    }
+
+    /// Get interface instance, if it's a global the create function hands out
+    ///
+    /// Interfaces instantiated on every call (`EXPOSE_INTERFACE`) have none.
+    pub fn get_instance(&self) -> Option<GenericAddress> {
+        self.instance
+    }
+
+    /// Whether `name` refers to this interface, exactly or without its version digits
+    pub fn matches(&self, name: &str) -> bool {
+        self.name
+            .strip_prefix(name)
+            .is_some_and(|version| version.bytes().all(|x| x.is_ascii_digit()))
+    }
+}
+
+/// Decode instructions at `address` in `module`, handing each to `f` till it
+/// returns something, or we hit a return
+fn scan_instructions<S: MemorySource + Clone, R>(
+    module: &Module<S>,
+    address: usize,
+    mut f: impl FnMut(usize, &Instruction) -> Option<R>,
+) -> InterfacesErrOr<R> {
+    if !module.contains(address) {
+        return Err(Error::CantFind.into());
+    }
+
+    let size = (MAX_INSTRUCTIONS * MAX_INSTRUCTION_LENGTH).min(module.get_end() - address);
+    let bytes = module.get_source().view(address, size)?;
+
+    for (address, instruction) in
+        x86::Decoder::new(&bytes, Mode::native(), address).take(MAX_INSTRUCTIONS)
+    {
+        if let Some(result) = f(address, &instruction) {
+            return Ok(result);
+        }
+
+        // `ret`/`ret imm16`, function is over
+        if instruction.get_map() == OpcodeMap::OneByte
+            && matches!(instruction.get_opcode(), 0xC2 | 0xC3)
+        {
+            break;
+        }
+    }
+
+    Err(Error::CantFind.into())
+}
+
+/// Get address of memory operand of `instruction` at `address`, if it's an
+/// absolute (32-bit) or RIP-relative (64-bit) one
+fn get_memory_operand(address: usize, instruction: &Instruction) -> Option<usize> {
+    let modrm = instruction.get_modrm()?;
+    if modrm >> 6 != 0 || modrm & 7 != 5 {
+        return None;
+    }
+
+    match instruction.get_mode() {
+        Mode::Bits32 => instruction
+            .get_displacement()
+            .map(|displacement| displacement.get_value() as usize),
+        Mode::Bits64 => instruction.rip_relative_target(address),
+    }
+}
+
+/// Get address of `s_pInterfaceRegs`, following `CreateInterface` into
+/// `CreateInterfaceInternal` and finding the first load from a global
+fn get_interface_regs<S: MemorySource + Clone>(module: &Module<S>) -> InterfacesErrOr<usize> {
+    let mut function = module
+        .get_exports()
+        .get("CreateInterface")
+        .ok_or(Error::CantFind)?
+        .exposed_addr();
+
+    for _ in 0..=MAX_THUNKS {
+        // Either `mov reg, [s_pInterfaceRegs]`, or a jump to follow
+        let found = scan_instructions(module, function, |address, instruction| {
+            match (instruction.get_map(), instruction.get_opcode()) {
+                (OpcodeMap::OneByte, 0x8B) => {
+                    get_memory_operand(address, instruction).map(|regs| (regs, false))
+                }
+                (OpcodeMap::OneByte, 0xE9 | 0xEB) => instruction
+                    .relative_target(address)
+                    .map(|target| (target, true)),
+                _ => None,
+            }
+        })?;
+
+        match found {
+            (target, true) => function = target,
+            (regs, false) => return Ok(regs),
+        }
+    }
+
+    Err(Error::CantFind.into())
+}
+
+/// Get instance a create function hands out, if it just returns the address
+/// of a global (`mov eax, imm32; ret` or `lea rax, [rip + disp32]; ret`)
+pub(crate) fn get_global_instance<S: MemorySource + Clone>(
+    module: &Module<S>,
+    create_fn: usize,
+) -> Option<usize> {
+    let mut instance = None;
+
+    scan_instructions(module, create_fn, |address, instruction| {
+        match (instance, instruction.get_map(), instruction.get_opcode()) {
+            (None, OpcodeMap::OneByte, 0xB8) => {
+                instance = instruction.get_immediate().map(|x| x.get_value() as usize);
+                None
+            }
+            (None, OpcodeMap::OneByte, 0x8D) => {
+                instance = instruction.rip_relative_target(address);
+                None
+            }
+            // Has to return right after
+            (Some(instance), OpcodeMap::OneByte, 0xC3) => Some(instance),
+            _ => Some(0),
+        }
+    })
+    .ok()
+    .filter(|&instance| instance != 0)
+}
+
+/// Get every interface `module` registered
+///
+/// # Example
+///
+/// ```ignore
+/// for interface in get_interfaces(&Module::new("engine.dll")?)? {
+///     println!("{} -> {:?}", interface.get_name(), interface.get_instance());
+/// }
+/// ```
+pub fn get_interfaces<S: MemorySource + Clone>(
+    module: &Module<S>,
+) -> InterfacesErrOr<Vec<Interface>> {
+    let source = module.get_source();
+    let mut result = Vec::new();
+
+    let mut it = source.read::<usize>(get_interface_regs(module)?)?;
+    while it != 0 && result.len() < MAX_INTERFACES {
+        let reg = source.read::<InterfaceReg>(it)?;
+
+        result.push(Interface {
+            name: source.read_c_string(reg.name, MAX_NAME_LENGTH)?,
+            create_fn: GenericAddress::from(reg.create_fn),
+            instance: get_global_instance(module, reg.create_fn).map(GenericAddress::from),
+        });
+
+        it = reg.next;
+    }
+
+    Ok(result)
+}
+
+/// Get interface registered by `module` as `name`, with or without version digits
+///
+/// # Example
+///
+/// ```ignore
+/// let client = find_interface(&Module::new("client.dll")?, "VClient")?;
+/// ```
+pub fn find_interface<S: MemorySource + Clone>(
+    module: &Module<S>,
+    name: &str,
+) -> InterfacesErrOr<Interface> {
+    get_interfaces(module)?
+        .into_iter()
+        .find(|interface| interface.matches(name))
+        .ok_or_else(|| Error::CantFind.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::memory::{
+        module_info::ModuleInfo,
+        snapshot::{Snapshot, SnapshotMemory},
+        source::{BufferMemory, Protection},
+    };
+    use std::mem::size_of;
+
+    const BASE: usize = 0x10000;
+    const IMAGE_SIZE: usize = 0x600;
+
+    const CREATE_INTERFACE: usize = BASE + 0x200;
+    const CREATE_INTERFACE_INTERNAL: usize = BASE + 0x240;
+    const CREATE_GLOBAL: usize = BASE + 0x260;
+    const CREATE_NEW: usize = BASE + 0x280;
+    const INTERFACE_REGS: usize = BASE + 0x500;
+    const GLOBAL: usize = BASE + 0x5C0;
+
+    fn put(bytes: &mut [u8], address: usize, value: &[u8]) {
+        let offset = address - BASE;
+        bytes[offset..offset + value.len()].copy_from_slice(value);
+    }
+
+    /// `mov ecx, [global]`, or `mov rcx, [rip + global]` on x64
+    fn load_global(address: usize, global: usize) -> Vec<u8> {
+        if cfg!(target_pointer_width = "64") {
+            let displacement = (global as isize - (address + 7) as isize) as i32;
+            [&[0x48, 0x8B, 0x0D][..], &displacement.to_le_bytes()].concat()
+        } else {
+            [&[0x8B, 0x0D][..], &(global as u32).to_le_bytes()].concat()
+        }
+    }
+
+    /// `mov eax, offset global`, or `lea rax, [rip + global]` on x64
+    fn address_of_global(address: usize, global: usize) -> Vec<u8> {
+        if cfg!(target_pointer_width = "64") {
+            let displacement = (global as isize - (address + 7) as isize) as i32;
+            [&[0x48, 0x8D, 0x05][..], &displacement.to_le_bytes()].concat()
+        } else {
+            [&[0xB8][..], &(global as u32).to_le_bytes()].concat()
+        }
+    }
+
+    /// Image as wide as we are, exporting `CreateInterface`, and registering
+    /// `VEngineCvar004` (a global) and `VClientEntityList003` (made per call)
+    fn image() -> Vec<u8> {
+        let mut bytes = vec![0; IMAGE_SIZE];
+        let (machine, magic, data_directory): (u16, u16, usize) = if size_of::<usize>() == 8 {
+            (0x8664, 0x20B, 0x70)
+        } else {
+            (0x14C, 0x10B, 0x60)
+        };
+
+        // Headers, with only an export directory
+        put(&mut bytes, BASE, b"MZ");
+        put(&mut bytes, BASE + 0x3C, &0x80u32.to_le_bytes());
+        put(&mut bytes, BASE + 0x80, b"PE\0\0");
+        put(&mut bytes, BASE + 0x84, &machine.to_le_bytes());
+        put(&mut bytes, BASE + 0x98, &magic.to_le_bytes());
+        put(
+            &mut bytes,
+            BASE + 0x98 + data_directory - 4,
+            &16u32.to_le_bytes(),
+        );
+        put(
+            &mut bytes,
+            BASE + 0x98 + data_directory,
+            &0x400u32.to_le_bytes(),
+        );
+
+        put(&mut bytes, BASE + 0x414, &1u32.to_le_bytes());
+        put(&mut bytes, BASE + 0x418, &1u32.to_le_bytes());
+        put(&mut bytes, BASE + 0x41C, &0x440u32.to_le_bytes());
+        put(&mut bytes, BASE + 0x420, &0x444u32.to_le_bytes());
+        put(&mut bytes, BASE + 0x424, &0x448u32.to_le_bytes());
+        put(&mut bytes, BASE + 0x440, &0x200u32.to_le_bytes());
+        put(&mut bytes, BASE + 0x444, &0x450u32.to_le_bytes());
+        put(&mut bytes, BASE + 0x450, b"CreateInterface\0");
+
+        // `CreateInterface` jumps to `CreateInterfaceInternal`, which walks the registry
+        let thunk = (CREATE_INTERFACE_INTERNAL - (CREATE_INTERFACE + 5)) as u32;
+        put(&mut bytes, CREATE_INTERFACE, &[0xE9]);
+        put(&mut bytes, CREATE_INTERFACE + 1, &thunk.to_le_bytes());
+        let load = load_global(CREATE_INTERFACE_INTERNAL, INTERFACE_REGS);
+        put(&mut bytes, CREATE_INTERFACE_INTERNAL, &load);
+        put(&mut bytes, CREATE_INTERFACE_INTERNAL + load.len(), &[0xC3]);
+
+        let global = address_of_global(CREATE_GLOBAL, GLOBAL);
+        put(&mut bytes, CREATE_GLOBAL, &global);
+        put(&mut bytes, CREATE_GLOBAL + global.len(), &[0xC3]);
+
+        // `push ebp; mov ebp, esp; pop ebp; ret`, as if it called `new`
+        put(&mut bytes, CREATE_NEW, &[0x55, 0x8B, 0xEC, 0x5D, 0xC3]);
+
+        // `s_pInterfaceRegs` and its list
+        let regs = [
+            (BASE + 0x520, CREATE_GLOBAL, BASE + 0x580, BASE + 0x540),
+            (BASE + 0x540, CREATE_NEW, BASE + 0x5A0, 0),
+        ];
+        put(&mut bytes, INTERFACE_REGS, &regs[0].0.to_le_bytes());
+        for (address, create_fn, name, next) in regs {
+            put(&mut bytes, address, &create_fn.to_le_bytes());
+            put(
+                &mut bytes,
+                address + size_of::<usize>(),
+                &name.to_le_bytes(),
+            );
+            put(
+                &mut bytes,
+                address + 2 * size_of::<usize>(),
+                &next.to_le_bytes(),
+            );
+        }
+        put(&mut bytes, BASE + 0x580, b"VEngineCvar004\0");
+        put(&mut bytes, BASE + 0x5A0, b"VClientEntityList003\0");
+
+        bytes
+    }
+
+    /// Module replayed from a snapshot of `bytes`, mapped at `BASE`
+    fn module(bytes: Vec<u8>) -> Module<SnapshotMemory> {
+        let memory = BufferMemory::new(BASE, bytes, Protection::READ_WRITE_EXECUTE);
+        let info = ModuleInfo::new(BASE, IMAGE_SIZE, String::new(), "engine.dll".to_owned());
+
+        let snapshot = Snapshot::capture(&memory, vec![info.clone()], &[]).unwrap();
+        info.to_module_with(SnapshotMemory::try_from(snapshot).unwrap())
+    }
+
+    #[test]
+    fn interfaces() {
+        let interfaces = get_interfaces(&module(image())).unwrap();
+
+        let names = interfaces
+            .iter()
+            .map(Interface::get_name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["VEngineCvar004", "VClientEntityList003"]);
+
+        assert_eq!(interfaces[0].get_create_fn().exposed_addr(), CREATE_GLOBAL);
+        assert_eq!(
+            interfaces[0].get_instance().map(|x| x.exposed_addr()),
+            Some(GLOBAL)
+        );
+        assert!(interfaces[1].get_instance().is_none());
+    }
+
+    #[test]
+    fn find() {
+        let module = module(image());
+
+        let find = |name| find_interface(&module, name).map(|x| x.get_name().to_owned());
+        assert_eq!(find("VEngineCvar").unwrap(), "VEngineCvar004");
+        assert_eq!(find("VEngineCvar004").unwrap(), "VEngineCvar004");
+        assert_eq!(find("VClientEntityList").unwrap(), "VClientEntityList003");
+
+        // Only version digits may follow
+        assert!(find("VEngine").is_err());
+        assert!(find("VModelInfoClient").is_err());
+    }
+
+    #[test]
+    fn no_create_interface() {
+        let mut bytes = image();
+        put(&mut bytes, BASE + 0x450, b"CreateInterfacf\0");
+
+        assert!(get_interfaces(&module(bytes)).is_err());
+    }
+}
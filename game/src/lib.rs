@@ -1,7 +1,10 @@
 #![feature(abi_thiscall)]
 #![allow(clippy::not_unsafe_ptr_arg_deref)]
 
-#[cfg(not(target_os = "windows"))]
-compile_error!("Windows support only");
-
+// Interfaces and entities are decoded from memory, so they work anywhere,
+// types call into the game and only make sense inside it
+pub mod entities;
+pub mod interfaces;
+pub mod signatures;
+#[cfg(windows)]
 pub mod types;
@@ -0,0 +1,18 @@
+//! Signatures module
+//!
+//! Shared between injected and external consumers, so both resolve the same
+//! objects the same way.
+//!
+//! Patterns are for the 32-bit client, they load globals through absolute
+//! addresses, which 64-bit code never holds. A 64-bit build compiles, but has to
+//! resolve these objects through `interfaces` instead.
+
+/// In `engine.dll`, CHLClient instance pointer pointer is 2 bytes in
+pub const CHL_CLIENT: &str = "8B 0D ? ? ? ? 8B 15 ? ? ? ? 8B";
+
+/// In `client.dll`'s `CHLClient::HudProcessInput`, IClientMode instance pointer
+/// pointer is 2 bytes in
+pub const CLIENT_MODE: &str = "8B 0D ? ? ? ? 8B 01";
+
+/// In `vguimatsurface.dll`, MatSystemSurface instance pointer pointer is 1 byte in
+pub const MAT_SYSTEM_SURFACE: &str = "A3 ? ? ? ? 83 3D ? ? ? ? ? 75 14 8B 04 B7 6A 00 68";
@@ -3,6 +3,7 @@
 #![allow(non_camel_case_types)]
 
 pub use crate::implement_vftable_holder_utilities;
+use std::ffi::c_int;
 
 /// Game CHLClient class partial implementation for usage
 #[repr(C)]
@@ -15,9 +16,64 @@ pub struct CHLClient(*mut CCHLClient);
 /// Indices enumerator for CCHLClient
 #[repr(isize)]
 pub enum CHLClientIndices {
+    /// Only forwards to the client mode, which it loads from a global first
+    HudProcessInput = 10,
     FrameStageNotify = 35,
 }
 
+/// Stage passed to CCHLClient's FrameStageNotify, `ClientFrameStage_t` in the SDK
+///
+/// Values the SDK doesn't know of are kept in `Unknown`, so converting back
+/// and forth never loses anything.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ClientFrameStage {
+    Undefined,
+    Start,
+    /// About to receive and apply a network update
+    NetUpdateStart,
+    /// Network data arrived, entities not yet updated with it
+    NetUpdatePostDataUpdateStart,
+    /// Entities updated with network data
+    NetUpdatePostDataUpdateEnd,
+    NetUpdateEnd,
+    /// About to render the scene
+    RenderStart,
+    RenderEnd,
+    Unknown(c_int),
+}
+
+impl From<c_int> for ClientFrameStage {
+    fn from(stage: c_int) -> Self {
+        match stage {
+            -1 => Self::Undefined,
+            0 => Self::Start,
+            1 => Self::NetUpdateStart,
+            2 => Self::NetUpdatePostDataUpdateStart,
+            3 => Self::NetUpdatePostDataUpdateEnd,
+            4 => Self::NetUpdateEnd,
+            5 => Self::RenderStart,
+            6 => Self::RenderEnd,
+            stage => Self::Unknown(stage),
+        }
+    }
+}
+
+impl From<ClientFrameStage> for c_int {
+    fn from(stage: ClientFrameStage) -> Self {
+        match stage {
+            ClientFrameStage::Undefined => -1,
+            ClientFrameStage::Start => 0,
+            ClientFrameStage::NetUpdateStart => 1,
+            ClientFrameStage::NetUpdatePostDataUpdateStart => 2,
+            ClientFrameStage::NetUpdatePostDataUpdateEnd => 3,
+            ClientFrameStage::NetUpdateEnd => 4,
+            ClientFrameStage::RenderStart => 5,
+            ClientFrameStage::RenderEnd => 6,
+            ClientFrameStage::Unknown(stage) => stage,
+        }
+    }
+}
+
 impl CHLClient {}
 
 implement_vftable_holder_utilities!(CHLClient);
@@ -0,0 +1,23 @@
+//! ClientMode type module
+
+#![allow(non_camel_case_types)]
+
+pub use crate::implement_vftable_holder_utilities;
+
+/// Game IClientMode class partial implementation for usage
+#[repr(C)]
+#[repr(packed(1))]
+pub struct CClientMode {}
+
+/// Rust-end structure for IClientMode
+pub struct ClientMode(*mut CClientMode);
+
+/// Indices enumerator for IClientMode
+#[repr(isize)]
+pub enum ClientModeIndices {
+    CreateMove = 21,
+}
+
+impl ClientMode {}
+
+implement_vftable_holder_utilities!(ClientMode);
@@ -3,7 +3,7 @@
 #![allow(non_camel_case_types)]
 
 pub use crate::implement_vftable_holder_utilities;
-use shared::call_virtual_function;
+use shared::{call_virtual_function, thiscall};
 use std::ffi::{c_int, c_uchar};
 
 /// Game CMatSystemSurface class partial implementation for usage
@@ -22,8 +22,9 @@ pub enum MatSystemSurfaceIndices {
 }
 
 // Game function types
-pub type SetDrawColor_t =
-    unsafe extern "thiscall" fn(*const usize, c_uchar, c_uchar, c_uchar, c_int) -> c_int;
+thiscall! {
+    pub type SetDrawColor_t = unsafe fn(*const usize, c_uchar, c_uchar, c_uchar, c_int) -> c_int;
+}
 
 impl MatSystemSurface {
     pub fn set_draw_color(&self, r: c_uchar, g: c_uchar, b: c_uchar, a: c_int) -> c_int {
@@ -1,6 +1,11 @@
 //! Types module
 
 pub mod chl_client;
+pub mod client_mode;
 pub mod convar;
 pub mod mat_system_surface;
+pub mod panel;
+pub mod shadow_vftable;
+pub mod user_cmd;
 pub mod vftable;
+pub mod vftable_slot;
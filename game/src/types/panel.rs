@@ -0,0 +1,47 @@
+//! Panel type module
+
+#![allow(non_camel_case_types)]
+
+pub use crate::implement_vftable_holder_utilities;
+use shared::{call_virtual_function, read_c_string, thiscall};
+use std::ffi::{c_schar, c_uint};
+
+/// Names of top-level panels drawn once per frame, over everything else
+pub const OVERLAY_PANEL_NAMES: [&str; 2] = ["MatSystemTopPanel", "FocusOverlayPanel"];
+
+/// Game handle to a vgui panel
+pub type VPANEL = c_uint;
+
+/// Game vgui::IPanel class partial implementation for usage
+#[repr(C)]
+#[repr(packed(1))]
+pub struct CPanel {}
+
+/// Rust-end structure for vgui::IPanel
+pub struct Panel(*mut CPanel);
+
+/// Indices enumerator for vgui::IPanel
+#[repr(isize)]
+pub enum PanelIndices {
+    GetName = 36,
+}
+
+// Game function types
+thiscall! {
+    type GetName_t = unsafe fn(*const usize, VPANEL) -> *const c_schar;
+}
+
+impl Panel {
+    /// Get name of `panel`, none if it has none
+    pub fn get_name(&self, panel: VPANEL) -> Option<String> {
+        read_c_string(call_virtual_function!(self.0, PanelIndices::GetName, GetName_t, panel) as _)
+    }
+
+    /// Whether `panel` is a top-level overlay panel, see `OVERLAY_PANEL_NAMES`
+    pub fn is_overlay(&self, panel: VPANEL) -> bool {
+        self.get_name(panel)
+            .is_some_and(|name| OVERLAY_PANEL_NAMES.contains(&name.as_str()))
+    }
+}
+
+implement_vftable_holder_utilities!(Panel);
@@ -0,0 +1,170 @@
+//! Shadow vftable module
+//!
+//! Hooks a single object by pointing it at a copy of its vftable, leaving the
+//! real table, the code it points to, and every other instance untouched.
+
+use crate::types::vftable::{GenericAddress, VfTable};
+use shared::{
+    error::Error,
+    memory::source::{LocalMemory, MemorySource},
+    GenericErrOr,
+};
+use std::mem::size_of;
+
+/// Type for shadow vftable errors
+pub type ShadowVfTableErrOr<T> = GenericErrOr<T>;
+
+/// Upper bound on virtual functions we copy, guards against running off the table
+pub const MAX_VIRTUAL_FUNCTIONS: usize = 1024;
+
+/// Per-instance vftable hook, swaps the instance's vptr for a copy of its
+/// vftable whose entries we can replace. Original vptr is put back on drop
+///
+/// The copy includes the slot right before the table, which MSVC uses for
+/// RTTI, so `dynamic_cast` and friends keep working on the hooked instance.
+///
+/// The copy is leaked on drop, pointed back at the original functions. A
+/// thread that loaded the shadow vptr right before we restored the real one
+/// may still read it, it can't be freed under it.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut shadow = ShadowVfTable::new(ctx.get_chl_client())?;
+/// let original = shadow.hook(
+///     CHLClientIndices::FrameStageNotify as usize,
+///     GenericAddress::from(frame_stage_notify as usize),
+/// )?;
+/// ```
+#[derive(Debug)]
+pub struct ShadowVfTable {
+    instance: usize,
+    /// Vptr instance had before we swapped it
+    original: usize,
+    /// RTTI slot, then the copied functions. Boxed so it never moves
+    table: Box<[usize]>,
+}
+
+/// Count functions of vftable at `table`, stopping at the first entry that
+/// doesn't point into executable memory
+fn count_virtual_functions(table: usize) -> usize {
+    (0..MAX_VIRTUAL_FUNCTIONS)
+        .take_while(|index| {
+            LocalMemory
+                .read::<usize>(table + index * size_of::<usize>())
+                .and_then(|function| LocalMemory.query(function))
+                .is_ok_and(|region| {
+                    region.is_committed() && region.get_protection().is_executable()
+                })
+        })
+        .count()
+}
+
+impl ShadowVfTable {
+    /// Shadow vftable of `object`
+    pub fn new(object: &impl VfTable) -> ShadowVfTableErrOr<Self> {
+        Self::new_with(object.get_instance())
+    }
+
+    /// Shadow vftable of object at `instance`
+    pub fn new_with(instance: GenericAddress) -> ShadowVfTableErrOr<Self> {
+        let instance = instance.exposed_addr();
+        let original = LocalMemory.read::<usize>(instance)?;
+
+        let count = count_virtual_functions(original);
+        if count == 0 {
+            return Err(Error::CantFind.into());
+        }
+
+        // Copy RTTI slot and functions in one go
+        let mut table = vec![0usize; count + 1].into_boxed_slice();
+        let bytes = unsafe {
+            std::slice::from_raw_parts_mut(
+                table.as_mut_ptr() as *mut u8,
+                table.len() * size_of::<usize>(),
+            )
+        };
+        LocalMemory.read_bytes_checked(original - size_of::<usize>(), bytes)?;
+
+        let result = Self {
+            instance,
+            original,
+            table,
+        };
+
+        // Point instance past the RTTI slot, like the real vptr
+        LocalMemory.write(instance, &result.get_table())?;
+
+        Ok(result)
+    }
+
+    /// Get hooked object address
+    pub fn get_instance(&self) -> GenericAddress {
+        GenericAddress::from(self.instance)
+    }
+
+    /// Get address of the shadow table, what the instance's vptr now is
+    pub fn get_table(&self) -> usize {
+        self.table[1..].as_ptr() as usize
+    }
+
+    /// Get number of virtual functions copied
+    pub fn get_count(&self) -> usize {
+        self.table.len() - 1
+    }
+
+    /// Get function the real vftable has at `index`
+    pub fn get_original(&self, index: usize) -> ShadowVfTableErrOr<GenericAddress> {
+        if index >= self.get_count() {
+            return Err(Error::CantFind.into());
+        }
+
+        Ok(GenericAddress::from(LocalMemory.read::<usize>(
+            self.original + index * size_of::<usize>(),
+        )?))
+    }
+
+    /// Point `index` of the shadow table at `function`, getting the original
+    /// to call through
+    pub fn hook(
+        &mut self,
+        index: usize,
+        function: GenericAddress,
+    ) -> ShadowVfTableErrOr<GenericAddress> {
+        let original = self.get_original(index)?;
+        self.table[index + 1] = function.exposed_addr();
+
+        Ok(original)
+    }
+
+    /// Point `index` of the shadow table back at the original function
+    pub fn unhook(&mut self, index: usize) -> ShadowVfTableErrOr<()> {
+        self.table[index + 1] = self.get_original(index)?.exposed_addr();
+        Ok(())
+    }
+
+    /// Whether `index` of the shadow table points somewhere else than the original
+    pub fn is_hooked(&self, index: usize) -> bool {
+        self.get_original(index)
+            .is_ok_and(|original| self.table[index + 1] != original.exposed_addr())
+    }
+}
+
+impl Drop for ShadowVfTable {
+    fn drop(&mut self) {
+        // Only restore if nobody swapped the vptr after us, or the instance is gone
+        if LocalMemory
+            .read::<usize>(self.instance)
+            .is_ok_and(|vptr| vptr == self.get_table())
+        {
+            let _ = LocalMemory.write(self.instance, &self.original);
+        }
+
+        // Late readers go straight to the originals
+        for index in 0..self.get_count() {
+            let _ = self.unhook(index);
+        }
+
+        std::mem::forget(std::mem::take(&mut self.table));
+    }
+}
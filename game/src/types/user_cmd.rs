@@ -0,0 +1,27 @@
+//! UserCmd type module
+
+use std::ffi::{c_float, c_int, c_short, c_uchar};
+
+/// Game CUserCmd class, one tick of input as sent to the server
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct UserCmd {
+    vftable: usize,
+    pub command_number: c_int,
+    pub tick_count: c_int,
+    /// Pitch, yaw and roll, in degrees
+    pub viewangles: [c_float; 3],
+    pub forwardmove: c_float,
+    pub sidemove: c_float,
+    pub upmove: c_float,
+    /// `IN_*` flags
+    pub buttons: c_int,
+    pub impulse: c_uchar,
+    pub weaponselect: c_int,
+    pub weaponsubtype: c_int,
+    pub random_seed: c_int,
+    pub mousedx: c_short,
+    pub mousedy: c_short,
+    /// Whether the client already predicted this command
+    pub hasbeenpredicted: bool,
+}
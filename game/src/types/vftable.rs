@@ -2,13 +2,48 @@
 
 pub use shared::get_virtual_function;
 pub use shared::memory::generic_address::GenericAddress;
+use std::{
+    marker::PhantomData,
+    mem::{size_of, transmute_copy},
+};
+
+/// Checks `F` is pointer sized at compile time, once `OK` is used
+struct AssertPointerSized<F>(PhantomData<F>);
+
+impl<F> AssertPointerSized<F> {
+    const OK: () = assert!(
+        size_of::<F>() == size_of::<usize>(),
+        "not a function pointer"
+    );
+}
+
+/// Turn function pointer `function` to an address, doesn't compile for types
+/// that aren't pointer sized
+pub fn function_address<F: Copy>(function: F) -> usize {
+    let () = AssertPointerSized::<F>::OK;
+    unsafe { transmute_copy::<F, usize>(&function) }
+}
+
+/// Turn `address` to function pointer `F`, doesn't compile for types that
+/// aren't pointer sized
+///
+/// # Safety
+///
+/// `address` has to be a function callable as `F`
+pub unsafe fn function_from_address<F: Copy>(address: usize) -> F {
+    let () = AssertPointerSized::<F>::OK;
+    transmute_copy::<usize, F>(&address)
+}
 
 pub trait VfTable {
     fn get_virtual_function(&self, index: isize) -> GenericAddress;
+
+    /// Get address of the object itself, whose first field is the vptr
+    fn get_instance(&self) -> GenericAddress;
 }
 
 // TODO: Rewrite this shit.
-/// Implements `get_function` using `self.0` for external usage.
+/// Implements `get_function` and `get_instance` using `self.0` for external usage.
 /// Also implements `From<*const ()>` and `Default` to facillitate
 /// the interop interface and avoid code repetition, by using the
 /// knowledge of `$class` being a tuple struct.
@@ -21,6 +56,10 @@ macro_rules! implement_vftable_holder_utilities {
                     $crate::types::vftable::get_virtual_function!(self.0, index) as *const (),
                 )
             }
+
+            fn get_instance(&self) -> $crate::types::vftable::GenericAddress {
+                $crate::types::vftable::GenericAddress::new(self.0 as *const ())
+            }
         }
 
         impl From<*const ()> for $class {
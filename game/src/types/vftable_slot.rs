@@ -0,0 +1,114 @@
+//! Vftable slot module
+//!
+//! Hooks a virtual function for every instance sharing a vftable, by
+//! overwriting its entry in the real table.
+
+use crate::types::vftable::{function_address, function_from_address, GenericAddress, VfTable};
+use shared::{
+    memory::{
+        protection::ProtectionGuard,
+        range::AddressRange,
+        source::{LocalMemory, MemorySource},
+    },
+    GenericErrOr,
+};
+use std::{marker::PhantomData, mem::size_of};
+
+/// Type for vftable slot errors
+pub type VfTableSlotErrOr<T> = GenericErrOr<T>;
+
+/// Single entry of a real vftable, swapped for another function until restored
+/// or dropped. `F` is the function pointer type of the entry
+///
+/// # Example
+///
+/// ```ignore
+/// let mut slot = VfTableSlot::<FrameStageNotify_t>::new(
+///     ctx.get_chl_client(),
+///     CHLClientIndices::FrameStageNotify as isize,
+/// )?;
+///
+/// slot.swap(frame_stage_notify)?;
+/// let original: FrameStageNotify_t = slot.get_original();
+/// ```
+#[derive(Debug)]
+pub struct VfTableSlot<F: Copy> {
+    /// Address of the entry in the vftable
+    slot: usize,
+    original: usize,
+    swapped: bool,
+    phantom_data: PhantomData<F>,
+}
+
+impl<F: Copy> VfTableSlot<F> {
+    /// Get slot `index` of `object`'s vftable
+    pub fn new(object: &impl VfTable, index: isize) -> VfTableSlotErrOr<Self> {
+        Self::new_with(object.get_instance(), index)
+    }
+
+    /// Get slot `index` of vftable of object at `instance`
+    pub fn new_with(instance: GenericAddress, index: isize) -> VfTableSlotErrOr<Self> {
+        let slot = GenericAddress::<()>::from(LocalMemory.read::<usize>(instance.exposed_addr())?)
+            .offset(index * size_of::<usize>() as isize)?
+            .exposed_addr();
+
+        Ok(Self {
+            slot,
+            original: LocalMemory.read::<usize>(slot)?,
+            swapped: false,
+            phantom_data: PhantomData,
+        })
+    }
+
+    /// Get address of the entry in the vftable
+    pub fn get_slot(&self) -> GenericAddress {
+        GenericAddress::from(self.slot)
+    }
+
+    /// Get function entry held before we touched it, to call through
+    pub fn get_original(&self) -> F {
+        // Entry held an `F` before we touched it
+        unsafe { function_from_address(self.original) }
+    }
+
+    /// Get original function entry as an address
+    pub fn get_original_address(&self) -> GenericAddress {
+        GenericAddress::from(self.original)
+    }
+
+    /// Whether entry currently holds our function
+    pub fn is_swapped(&self) -> bool {
+        self.swapped
+    }
+
+    /// Point entry at `function`
+    pub fn swap(&mut self, function: F) -> VfTableSlotErrOr<()> {
+        self.write(function_address(function))?;
+        self.swapped = true;
+        Ok(())
+    }
+
+    /// Point entry back at the original function
+    pub fn restore(&mut self) -> VfTableSlotErrOr<()> {
+        if self.swapped {
+            self.write(self.original)?;
+            self.swapped = false;
+        }
+
+        Ok(())
+    }
+
+    /// Write `function` to the entry, vftables live in read-only data
+    fn write(&self, function: usize) -> VfTableSlotErrOr<()> {
+        let _guard =
+            ProtectionGuard::writable(AddressRange::with_size(self.slot, size_of::<usize>())?)?;
+
+        LocalMemory.write(self.slot, &function)
+    }
+}
+
+impl<F: Copy> Drop for VfTableSlot<F> {
+    fn drop(&mut self) {
+        let _ = self.restore();
+    }
+}
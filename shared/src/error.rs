@@ -1,3 +1,4 @@
+use crate::memory::range::AddressRange;
 use std::{
     error,
     fmt::{self, Display, Formatter},
@@ -12,6 +13,20 @@ pub enum Error {
     NoSentinelCharacter,
     /// Can't find something, generally in memory scanning
     CantFind,
+    /// Bytes don't decode to a valid instruction, or the instruction is truncated
+    InvalidInstruction,
+    /// Gave up waiting on something, generally a module load
+    Timeout,
+    /// Memory isn't committed or doesn't allow the access we attempted
+    AccessViolation,
+    /// Snapshot file has a bad magic, an unsupported version, or is truncated
+    InvalidSnapshot,
+    /// Address arithmetic overflowed or underflowed
+    Overflow,
+    /// Attempted to wait on the loader while holding its lock
+    LoaderLock,
+    /// Target's pointers aren't as wide as ours, so its structures have another layout
+    PointerWidthMismatch,
 }
 
 impl error::Error for Error {
@@ -20,6 +35,13 @@ impl error::Error for Error {
             Error::NullPointer => "null pointer",
             Error::NoSentinelCharacter => "no sentinel character",
             Error::CantFind => "failed a find operation",
+            Error::InvalidInstruction => "invalid instruction",
+            Error::Timeout => "timed out",
+            Error::AccessViolation => "access violation",
+            Error::InvalidSnapshot => "invalid snapshot",
+            Error::Overflow => "overflow",
+            Error::LoaderLock => "loader lock held",
+            Error::PointerWidthMismatch => "pointer width mismatch",
         }
     }
 }
@@ -33,7 +55,140 @@ impl Display for Error {
                 Error::NullPointer => "Encountered a null pointer (most often happens in dereferencing operation)",
                 Error::NoSentinelCharacter => "Encountered a string interacting with a C API, without a sentinel character (most often null terminator)",
                 Error::CantFind => "Failed to perform a find operation (most often memory related)",
+                Error::InvalidInstruction => "Encountered bytes that don't decode to a valid instruction (most often decoding data or a truncated buffer)",
+                Error::Timeout => "Timed out waiting on an operation (most often waiting for a module to load)",
+                Error::AccessViolation => "Attempted to access memory that isn't committed or doesn't allow it (most often a stale address)",
+                Error::InvalidSnapshot => "Encountered a snapshot that's malformed or from an unsupported version (most often a truncated or foreign file)",
+                Error::Overflow => "Address arithmetic overflowed (most often a bad offset, or an alignment of zero)",
+                Error::LoaderLock => "Attempted to wait on the loader while holding its lock (most often waiting for a module from DllMain)",
+                Error::PointerWidthMismatch => "Encountered a target whose pointers aren't as wide as ours (most often a 64-bit build reading 32-bit TF2)",
             }
         )
     }
 }
+
+/// Error for pointer paths, says which hop of which path failed and why
+#[derive(Debug)]
+pub struct PointerPathError {
+    path: String,
+    hop: usize,
+    address: usize,
+    cause: Box<dyn error::Error>,
+}
+
+impl PointerPathError {
+    pub fn new(path: String, hop: usize, address: usize, cause: Box<dyn error::Error>) -> Self {
+        Self {
+            path,
+            hop,
+            address,
+            cause,
+        }
+    }
+
+    /// Get rendered path that failed
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+
+    /// Get index of hop that failed, 0 being the read at the base
+    pub fn get_hop(&self) -> usize {
+        self.hop
+    }
+
+    /// Get address hop failed to read from
+    pub fn get_address(&self) -> usize {
+        self.address
+    }
+
+    /// Get underlying error
+    pub fn get_cause(&self) -> &dyn error::Error {
+        self.cause.as_ref()
+    }
+}
+
+impl error::Error for PointerPathError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(self.cause.as_ref())
+    }
+}
+
+impl Display for PointerPathError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(Shared): Failed following pointer path {} at hop {} (reading {:#x}), caused by {}",
+            self.path, self.hop, self.address, self.cause
+        )
+    }
+}
+
+/// Error for byte patches, says which patch failed and why
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PatchError {
+    /// Bytes under a patch aren't the ones it was written against (most often a game update)
+    Mismatch {
+        name: String,
+        address: usize,
+        expected: Vec<Option<u8>>,
+        found: Vec<u8>,
+    },
+    /// Patch overlaps another one, or reuses its name
+    Conflict {
+        name: String,
+        other: String,
+        range: AddressRange,
+    },
+    /// Expected and replacement bytes differ in length, or are empty
+    InvalidSize {
+        name: String,
+        expected: usize,
+        replacement: usize,
+    },
+}
+
+/// Format `bytes` the way patterns are written, `55 8B ?? EC`, `None` being
+/// any byte
+fn format_bytes(bytes: impl IntoIterator<Item = Option<u8>>) -> String {
+    bytes
+        .into_iter()
+        .map(|x| x.map_or_else(|| "??".to_owned(), |x| format!("{:02X}", x)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl error::Error for PatchError {}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::Mismatch {
+                name,
+                address,
+                expected,
+                found,
+            } => write!(
+                f,
+                "(Shared): Patch {} expected {} at {:#x}, found {} (most often a game update moved or changed the code)",
+                name,
+                format_bytes(expected.iter().copied()),
+                address,
+                format_bytes(found.iter().copied().map(Some))
+            ),
+            PatchError::Conflict { name, other, range } => write!(
+                f,
+                "(Shared): Patch {} at {} conflicts with patch {} (overlapping ranges, or same name)",
+                name, range, other
+            ),
+            PatchError::InvalidSize {
+                name,
+                expected,
+                replacement,
+            } => write!(
+                f,
+                "(Shared): Patch {} expects {} bytes but replaces them with {}",
+                name, expected, replacement
+            ),
+        }
+    }
+}
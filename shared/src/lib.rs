@@ -3,21 +3,34 @@
 #![feature(unchecked_math)]
 #![feature(abi_thiscall)]
 
-#[cfg(not(target_os = "windows"))]
-compile_error!("Windows support only");
-
+// Only what talks to Windows is Windows only, the rest builds, and is tested, anywhere
+#[cfg(windows)]
 pub mod console;
 pub mod error;
 pub mod memory;
+#[cfg(windows)]
+pub mod process;
 use std::error::Error;
 pub use std::ffi::{c_schar, c_uchar};
-pub use winapi::shared::minwindef::{BOOL, DWORD, HMODULE, LPVOID};
+#[cfg(windows)]
+use std::{sync::mpsc, thread, time::Duration};
+#[cfg(windows)]
 use winapi::um::winbase::lstrlenA;
+#[cfg(windows)]
+pub use winapi::{
+    shared::minwindef::{BOOL, DWORD, HMODULE, LPVOID},
+    um::winnt::{DLL_PROCESS_ATTACH, DLL_PROCESS_DETACH},
+};
 
 /// Allow for error decayal so we can work with multiple error types
 pub type GenericErrOr<T> = Result<T, Box<dyn Error>>;
 
 /// Wrap windows shared library entry point to only take a closure
+///
+/// The `thread` form initializes off the loader lock, and hands `$detach` the
+/// module and `lpReserved`, which isn't null when the process is exiting.
+/// Other threads were killed wherever they were by then, so don't wait on them,
+/// or on locks they may have held.
 #[macro_export]
 macro_rules! entry_point {
     ($f: expr) => {
@@ -31,6 +44,88 @@ macro_rules! entry_point {
             $f(module, reason_for_call as u32).into()
         }
     };
+    (thread $init: expr, $timeout: expr, $failure: expr, $detach: expr) => {
+        #[no_mangle]
+        unsafe extern "stdcall" fn DllMain(
+            module: $crate::HMODULE,
+            reason_for_call: $crate::DWORD,
+            reserved: $crate::LPVOID,
+        ) -> $crate::BOOL {
+            match reason_for_call {
+                // Loader lock is held here, so do nothing but start a thread
+                $crate::DLL_PROCESS_ATTACH => {
+                    $crate::spawn_initialization(module, $timeout, $init, $failure);
+                }
+                $crate::DLL_PROCESS_DETACH => $detach(module, reserved),
+                _ => {}
+            }
+
+            true.into()
+        }
+    };
+}
+
+/// Run `initialize` on a new thread, off the loader lock, calling `failure`
+/// after reporting its error if it fails
+///
+/// If `timeout` elapses first it's reported, and we keep waiting, as there's
+/// no safe way to stop initialization midway.
+///
+/// # Example
+///
+/// ```ignore
+/// entry_point!(
+///     thread initialize,
+///     Some(Duration::from_secs(120)),
+///     |module| eject(module),
+///     |_, reserved: LPVOID| {
+///         if reserved.is_null() {
+///             uninitialize();
+///         }
+///     }
+/// );
+/// ```
+#[cfg(windows)]
+pub fn spawn_initialization<I, F>(
+    module: HMODULE,
+    timeout: Option<Duration>,
+    initialize: I,
+    failure: F,
+) where
+    I: FnOnce(HMODULE) -> GenericErrOr<()> + Send + 'static,
+    F: FnOnce(HMODULE) + Send + 'static,
+{
+    // Module handles aren't `Send`, the address is all we need
+    let module = module as usize;
+
+    thread::spawn(move || {
+        let (sender, receiver) = mpsc::channel();
+
+        // Errors aren't `Send` either, so they're rendered where they happen
+        thread::spawn(move || {
+            let _ = sender.send(initialize(module as HMODULE).map_err(|error| error.to_string()));
+        });
+
+        let result = match timeout.map(|timeout| receiver.recv_timeout(timeout)) {
+            Some(Err(mpsc::RecvTimeoutError::Timeout)) => {
+                println!("Initialization failed: {}", error::Error::Timeout);
+                println!("Waiting on initialization anyway");
+                receiver.recv()
+            }
+            Some(result) => result.map_err(|_| mpsc::RecvError),
+            None => receiver.recv(),
+        };
+
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(error)) => {
+                println!("Initialization failed: {}", error);
+                failure(module as HMODULE);
+            }
+            // Initialization panicked, which was already reported
+            Err(_) => failure(module as HMODULE),
+        }
+    });
 }
 
 /// Syntactical sugar for `*const c_schar`
@@ -41,15 +136,64 @@ macro_rules! c_str {
     };
 }
 
+/// Declares member function types and definitions with the game's calling
+/// convention, `thiscall` on x86 and the only one there is on x86-64
+///
+/// # Example
+///
+/// ```ignore
+/// thiscall! {
+///     type GetName_t = unsafe fn(*const usize, VPANEL) -> *const c_schar;
+///
+///     unsafe fn get_name(this: *const usize, panel: VPANEL) -> *const c_schar {
+///         GET_NAME.get()(this, panel)
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! thiscall {
+    () => {};
+    (
+        $(#[$meta:meta])*
+        $vis:vis type $name:ident = unsafe fn($($arg:ty),* $(,)?) $(-> $ret:ty)?;
+        $($rest:tt)*
+    ) => {
+        #[cfg(target_arch = "x86")]
+        $(#[$meta])*
+        $vis type $name = unsafe extern "thiscall" fn($($arg),*) $(-> $ret)?;
+        #[cfg(not(target_arch = "x86"))]
+        $(#[$meta])*
+        $vis type $name = unsafe extern "C" fn($($arg),*) $(-> $ret)?;
+
+        $crate::thiscall!($($rest)*);
+    };
+    (
+        $(#[$meta:meta])*
+        $vis:vis unsafe fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)? $body:block
+        $($rest:tt)*
+    ) => {
+        #[cfg(target_arch = "x86")]
+        $(#[$meta])*
+        $vis unsafe extern "thiscall" fn $name($($arg: $ty),*) $(-> $ret)? $body
+        #[cfg(not(target_arch = "x86"))]
+        $(#[$meta])*
+        $vis unsafe extern "C" fn $name($($arg: $ty),*) $(-> $ret)? $body
+
+        $crate::thiscall!($($rest)*);
+    };
+}
+
 /// Turns null-terminated UTF-8 array `raw` to `String`, if not nil or others
 ///
 /// # Example
 ///
 /// ```rust
-/// if let Some(rs) = read_c_string(&['a', 'b', 'c', '\0'].as_ptr() as _) {
+/// # use shared::read_c_string;
+/// if let Some(rs) = read_c_string(b"abc\0".as_ptr()) {
 ///     println!("Hello {}!", rs)
 /// }
 /// ```
+#[cfg(windows)]
 #[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub fn read_c_string(raw: *const c_uchar) -> Option<String> {
     unsafe {
@@ -1,7 +1,17 @@
 //! Generic address module
 
-use crate::{error::Error, GenericErrOr};
-use std::marker::PhantomData;
+#[cfg(windows)]
+use crate::memory::source::LocalMemory;
+use crate::{
+    error::Error,
+    memory::{pod::Pod, pointer_path::PointerPath, source::MemorySource},
+    GenericErrOr,
+};
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
 
 /// Type for generic address errors
 pub type GenericAddressErrOr<T> = GenericErrOr<T>;
@@ -61,13 +71,14 @@ impl<T> GenericAddress<T> {
         }
     }
 
-    /// Offset from `resource` by `offset` bytes
-    ///
-    /// # Safety
-    ///
-    /// Unwraps `checked_add_signed`
+    /// Offset from `resource` by `offset` bytes, if it neither overflows nor
+    /// lands on null
     pub fn offset(self, offset: isize) -> GenericAddressErrOr<Self> {
-        let add = self.exposed_addr().checked_add_signed(offset).unwrap();
+        let add = self
+            .exposed_addr()
+            .checked_add_signed(offset)
+            .ok_or(Error::Overflow)?;
+
         if add == 0 {
             Err(Error::NullPointer.into())
         } else {
@@ -75,10 +86,68 @@ impl<T> GenericAddress<T> {
         }
     }
 
-    /// Dereference `resource` `branches` times
-    pub fn deref(mut self, mut branches: usize) -> GenericAddressErrOr<Self> {
+    /// Go back from `resource` by `offset` bytes, if it doesn't underflow
+    pub fn checked_sub(self, offset: usize) -> GenericAddressErrOr<Self> {
+        self.exposed_addr()
+            .checked_sub(offset)
+            .map(Self::from)
+            .ok_or_else(|| Error::Overflow.into())
+    }
+
+    /// Round `resource` down to a multiple of `alignment`, which can't be zero
+    pub fn align_down(self, alignment: usize) -> GenericAddressErrOr<Self> {
+        let address = self.exposed_addr();
+        let remainder = address.checked_rem(alignment).ok_or(Error::Overflow)?;
+
+        Ok(Self::from(address - remainder))
+    }
+
+    /// Round `resource` up to a multiple of `alignment`, which can't be zero
+    pub fn align_up(self, alignment: usize) -> GenericAddressErrOr<Self> {
+        let address = self.exposed_addr();
+
+        match address.checked_rem(alignment).ok_or(Error::Overflow)? {
+            0 => Ok(self),
+            remainder => address
+                .checked_add(alignment - remainder)
+                .map(Self::from)
+                .ok_or_else(|| Error::Overflow.into()),
+        }
+    }
+
+    /// Whether `resource` is a multiple of `alignment`, never for zero
+    pub fn is_aligned(&self, alignment: usize) -> bool {
+        self.exposed_addr().checked_rem(alignment) == Some(0)
+    }
+
+    /// Get signed distance from `resource` to `other`, so that
+    /// `self.offset(self.distance(other)?)` is `other`
+    pub fn distance<R>(&self, other: GenericAddress<R>) -> GenericAddressErrOr<isize> {
+        let (from, to) = (self.exposed_addr(), other.exposed_addr());
+        let distance = to.wrapping_sub(from) as isize;
+
+        // Wrapped if it doesn't take us back, distance doesn't fit `isize`
+        if from.checked_add_signed(distance) == Some(to) {
+            Ok(distance)
+        } else {
+            Err(Error::Overflow.into())
+        }
+    }
+
+    /// Dereference `resource` `branches` times, checking every read
+    #[cfg(windows)]
+    pub fn deref(self, branches: usize) -> GenericAddressErrOr<Self> {
+        self.deref_from(&LocalMemory, branches)
+    }
+
+    /// Dereference `resource` `branches` times through `source`, checking every read
+    pub fn deref_from<S: MemorySource>(
+        mut self,
+        source: &S,
+        mut branches: usize,
+    ) -> GenericAddressErrOr<Self> {
         while branches > 0 {
-            self.resource = unsafe { *(self.resource as *const _) };
+            self.resource = source.read::<usize>(self.exposed_addr())? as _;
             if self.resource.is_null() {
                 return Err(Error::NullPointer.into());
             }
@@ -88,6 +157,45 @@ impl<T> GenericAddress<T> {
 
         Ok(self)
     }
+
+    /// Get pointer path starting at `resource`, see `PointerPath`
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // *(*(*client + 0x10) + 0x4) + 0x8
+    /// let health = client.path::<i32>(&[0x10, 0x4, 0x8]).read()?;
+    /// ```
+    #[allow(invalid_type_param_default)]
+    pub fn path<R = T>(self, offsets: &[isize]) -> PointerPath<R> {
+        PointerPath::new(self.exposed_addr(), offsets)
+    }
+
+    /// Read `R` at `resource`, if the memory is committed and readable
+    #[cfg(windows)]
+    pub fn read<R: Pod>(&self) -> GenericAddressErrOr<R> {
+        self.read_from(&LocalMemory)
+    }
+
+    /// Read `R` at `resource` through `source`, if the memory is committed and readable
+    pub fn read_from<S: MemorySource, R: Pod>(&self, source: &S) -> GenericAddressErrOr<R> {
+        source.read(self.exposed_addr())
+    }
+
+    /// Write `value` at `resource`, if the memory is committed and writable
+    #[cfg(windows)]
+    pub fn write<R: Pod>(&self, value: R) -> GenericAddressErrOr<()> {
+        self.write_to(&LocalMemory, value)
+    }
+
+    /// Write `value` at `resource` through `source`, if the memory is committed and writable
+    pub fn write_to<R: Pod, S: MemorySource>(
+        &self,
+        source: &S,
+        value: R,
+    ) -> GenericAddressErrOr<()> {
+        source.write(self.exposed_addr(), &value)
+    }
 }
 
 impl<T> From<usize> for GenericAddress<T> {
@@ -95,3 +203,139 @@ impl<T> From<usize> for GenericAddress<T> {
         Self::new(value as _)
     }
 }
+
+// Compared, ordered and hashed by address alone, whatever `T` is
+
+impl<T> PartialEq for GenericAddress<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.exposed_addr() == other.exposed_addr()
+    }
+}
+
+impl<T> Eq for GenericAddress<T> {}
+
+impl<T> PartialOrd for GenericAddress<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for GenericAddress<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.exposed_addr().cmp(&other.exposed_addr())
+    }
+}
+
+impl<T> Hash for GenericAddress<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.exposed_addr().hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{hash_map::DefaultHasher, HashSet};
+
+    fn address(value: usize) -> GenericAddress {
+        GenericAddress::from(value)
+    }
+
+    fn error<R: std::fmt::Debug>(result: GenericAddressErrOr<R>) -> Error {
+        *result.unwrap_err().downcast_ref::<Error>().unwrap()
+    }
+
+    #[test]
+    fn offset() {
+        assert_eq!(address(0x1000).offset(0x10).unwrap(), address(0x1010));
+        assert_eq!(address(0x1000).offset(-0x10).unwrap(), address(0xFF0));
+
+        assert_eq!(error(address(usize::MAX).offset(1)), Error::Overflow);
+        assert_eq!(error(address(0x10).offset(-0x11)), Error::Overflow);
+        assert_eq!(error(address(0x10).offset(-0x10)), Error::NullPointer);
+    }
+
+    #[test]
+    fn checked_sub() {
+        assert_eq!(address(0x1000).checked_sub(0x10).unwrap(), address(0xFF0));
+        assert_eq!(address(0x1000).checked_sub(0x1000).unwrap(), address(0));
+        assert_eq!(error(address(0x1000).checked_sub(0x1001)), Error::Overflow);
+    }
+
+    #[test]
+    fn align() {
+        assert_eq!(address(0x1234).align_down(0x1000).unwrap(), address(0x1000));
+        assert_eq!(address(0x1234).align_up(0x1000).unwrap(), address(0x2000));
+        assert_eq!(address(0x2000).align_up(0x1000).unwrap(), address(0x2000));
+        assert_eq!(address(0x2000).align_down(0x1000).unwrap(), address(0x2000));
+
+        // Any alignment works, not only powers of two
+        assert_eq!(address(100).align_down(12).unwrap(), address(96));
+        assert_eq!(address(100).align_up(12).unwrap(), address(108));
+
+        assert_eq!(error(address(0x1234).align_down(0)), Error::Overflow);
+        assert_eq!(error(address(0x1234).align_up(0)), Error::Overflow);
+        assert_eq!(error(address(usize::MAX).align_up(0x1000)), Error::Overflow);
+    }
+
+    #[test]
+    fn is_aligned() {
+        assert!(address(0x2000).is_aligned(0x1000));
+        assert!(!address(0x2001).is_aligned(0x1000));
+        assert!(address(96).is_aligned(12));
+        assert!(address(0).is_aligned(8));
+        assert!(!address(0x2000).is_aligned(0));
+    }
+
+    #[test]
+    fn distance() {
+        let (low, high) = (address(0x1000), address(0x1800));
+        assert_eq!(low.distance(high).unwrap(), 0x800);
+        assert_eq!(high.distance(low).unwrap(), -0x800);
+        assert_eq!(low.distance(low).unwrap(), 0);
+        assert_eq!(low.offset(low.distance(high).unwrap()).unwrap(), high);
+
+        // Further apart than `isize` reaches
+        assert_eq!(
+            error(address(0).distance(address(usize::MAX))),
+            Error::Overflow
+        );
+        assert_eq!(
+            error(address(usize::MAX).distance(address(0))),
+            Error::Overflow
+        );
+    }
+
+    #[test]
+    fn compare_and_hash() {
+        /// Neither comparable nor hashable itself
+        struct Opaque;
+
+        let hash = |address: GenericAddress<Opaque>| {
+            let mut hasher = DefaultHasher::new();
+            address.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        let opaque = GenericAddress::<Opaque>::from(0x1000);
+        assert!(opaque == GenericAddress::from(0x1000));
+        assert!(opaque != GenericAddress::from(0x1001));
+        assert_eq!(hash(opaque), hash(GenericAddress::from(0x1000)));
+
+        assert!(address(0x1000) < address(0x1001));
+        assert_eq!(address(0x1000).cmp(&address(0x1000)), Ordering::Equal);
+        assert_eq!(
+            address(0x2000).partial_cmp(&address(0x1000)),
+            Some(Ordering::Greater)
+        );
+
+        let mut sorted = vec![address(0x3000), address(0x1000), address(0x2000)];
+        sorted.sort();
+        assert_eq!(sorted, [address(0x1000), address(0x2000), address(0x3000)]);
+
+        let set = [address(0x1000), address(0x1000), address(0x2000)]
+            .into_iter()
+            .collect::<HashSet<_>>();
+        assert_eq!(set.len(), 2);
+    }
+}
@@ -3,3 +3,17 @@
 pub mod cpp;
 pub mod generic_address;
 pub mod module;
+pub mod module_info;
+#[cfg(windows)]
+pub mod notification;
+pub mod patch;
+pub mod pattern;
+pub mod pod;
+pub mod pointer_path;
+#[cfg(windows)]
+pub mod protection;
+pub mod range;
+pub mod regions;
+pub mod snapshot;
+pub mod source;
+pub mod x86;
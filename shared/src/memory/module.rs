@@ -1,196 +1,688 @@
 //! Module for project modules
 
-use crate::{error::Error, memory::generic_address::GenericAddress, read_c_string, GenericErrOr};
+pub use crate::memory::{
+    module_info::ModuleInfo,
+    pattern::{find_nth_pattern_in_slice, PATTERN_WILDCARD},
+};
+#[cfg(windows)]
+use crate::memory::{notification::LoadNotification, source::LocalMemory};
+use crate::{
+    error::Error,
+    memory::{
+        generic_address::GenericAddress, pod::Pod, range::AddressRange, source::MemorySource,
+        x86::Mode,
+    },
+    GenericErrOr,
+};
+#[cfg(all(windows, target_arch = "x86"))]
 use ntapi::winapi_local::um::winnt::__readfsdword;
+#[cfg(all(windows, target_arch = "x86_64"))]
+use ntapi::winapi_local::um::winnt::__readgsqword;
+#[cfg(windows)]
+use ntapi::{
+    ntldr::{LdrLockLoaderLock, LdrUnlockLoaderLock},
+    ntrtl::RtlIsCriticalSectionLockedByThread,
+};
+use std::{collections::hash_map::HashMap, mem::size_of};
+#[cfg(windows)]
 use std::{
-    collections::hash_map::HashMap,
-    ffi::{c_schar, OsStr},
-    os::windows::prelude::OsStrExt,
+    ptr::null_mut,
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
 };
+#[cfg(windows)]
 use winapi::{
-    shared::ntdef::{PVOID, ULONG, UNICODE_STRING},
-    um::{
-        winbase::lstrcmpiW,
-        winnt::{
-            IMAGE_DIRECTORY_ENTRY_EXPORT, PIMAGE_DOS_HEADER, PIMAGE_EXPORT_DIRECTORY,
-            PIMAGE_NT_HEADERS,
-        },
-    },
+    shared::ntdef::{NT_SUCCESS, PVOID},
+    um::winnt::PRTL_CRITICAL_SECTION,
 };
 
 /// Exports type
 pub type Exports = HashMap<String, GenericAddress>;
 
-/// Module view
+/// Module view, over our own process' memory unless told otherwise
+///
+/// Only the loader list and our own memory are Windows specific, parsing
+/// and scanning work on any memory source.
 #[derive(Debug)]
-pub struct Module {
-    start: usize,
-    end: usize,
+pub struct Module<
+    #[cfg(windows)] S: MemorySource = LocalMemory,
+    #[cfg(not(windows))] S: MemorySource,
+> {
+    source: S,
+    range: AddressRange,
     /// Holds export address table, gives you virtual address held
     /// in generic addresses container, so you can cast to any type,
     /// or transmute.
     exports: Exports,
+    sections: Vec<Section>,
+}
+
+/// Module section, as described by its PE section header
+#[derive(Debug, Clone)]
+pub struct Section {
+    name: String,
+    range: AddressRange,
+    characteristics: u32,
+}
+
+impl Section {
+    /// Get section name (e.g. `.text`)
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get addresses section is mapped at
+    pub fn get_range(&self) -> AddressRange {
+        self.range
+    }
+
+    /// Get `IMAGE_SCN_*` flags
+    pub fn get_characteristics(&self) -> u32 {
+        self.characteristics
+    }
+
+    /// Whether section holds code
+    pub fn is_executable(&self) -> bool {
+        self.characteristics & SECTION_MEM_EXECUTE != 0
+    }
 }
 
 /// Type for module errors
 pub type ModuleErrOr<T> = GenericErrOr<T>;
 
+/// How often `Module::wait_for` checks the loader list, even without notifications
+#[cfg(windows)]
+pub const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+impl ModuleInfo {
+    /// Get full module view, parsing exports
+    #[cfg(windows)]
+    pub fn to_module(&self) -> Module {
+        Module::new_with(self.get_base(), self.get_end())
+    }
+
+    /// Get full module view read through `source`, parsing exports
+    pub fn to_module_with<S: MemorySource + Clone>(&self, source: S) -> Module<S> {
+        Module::new_with_source(source, self.get_base(), self.get_end())
+    }
+}
+
 /// List entry
+#[derive(Clone, Copy)]
 #[repr(C)]
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
+#[allow(dead_code)]
 struct LIST_ENTRY {
-    pub Flink: *mut LIST_ENTRY,
-    pub Blink: *mut LIST_ENTRY,
+    pub Flink: usize,
+    pub Blink: usize,
+}
+
+/// Counted UTF-16 string, as the loader keeps names
+#[derive(Clone, Copy)]
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[allow(non_snake_case)]
+#[allow(dead_code)]
+struct UNICODE_STRING {
+    pub Length: u16,
+    pub MaximumLength: u16,
+    pub Buffer: usize,
 }
 
 /// Ldr data entry table for module information
+#[derive(Clone, Copy)]
 #[repr(C)]
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
+#[allow(dead_code)]
 struct LDR_DATA_TABLE_ENTRY {
     pub InMemoryOrderModuleList: LIST_ENTRY,
-    pub BaseAddress: PVOID,
-    pub EntryPoint: PVOID,
-    pub SizeOfImage: ULONG,
+    pub BaseAddress: usize,
+    pub EntryPoint: usize,
+    pub SizeOfImage: u32,
     pub FullDllName: UNICODE_STRING,
     pub BaseDllName: UNICODE_STRING,
 }
 
-/// Walk process' loaded modules, trying to match for our goal `module`
-fn get_module_range(module: &str) -> ModuleErrOr<Module> {
-    if !module.ends_with('\0') {
-        return Err(Error::NoSentinelCharacter.into());
-    }
+/// Offset of `Ldr` in `PEB`
+#[cfg(target_arch = "x86")]
+const PEB_LDR_OFFSET: usize = 0xC;
+#[cfg(target_arch = "x86_64")]
+const PEB_LDR_OFFSET: usize = 0x18;
 
-    // https://users.rust-lang.org/t/how-copy-string-to-c-style-wchar-array/12283
+/// Offset of `InInitializationOrderModuleList` in `PEB_LDR_DATA`
+#[cfg(target_arch = "x86")]
+const LDR_INITIALIZATION_ORDER_OFFSET: usize = 0x1C;
+#[cfg(target_arch = "x86_64")]
+const LDR_INITIALIZATION_ORDER_OFFSET: usize = 0x30;
 
-    fn encode_as_wchar(str: &str) -> Vec<u16> {
-        OsStr::new(str)
-            .encode_wide() // get as wide string
-            .chain(Some(0).into_iter()) // add null terminator
-            .collect()
-    }
+/// Offset of `LoaderLock` in `PEB`
+#[cfg(all(windows, target_arch = "x86"))]
+const PEB_LOADER_LOCK_OFFSET: usize = 0xA0;
+#[cfg(all(windows, target_arch = "x86_64"))]
+const PEB_LOADER_LOCK_OFFSET: usize = 0x110;
 
-    // Get wide-encoded module
-    let wide_module = encode_as_wchar(module).as_ptr();
+/// Get current process' PEB, through the TEB
+#[cfg(windows)]
+fn get_peb() -> usize {
+    #[cfg(target_arch = "x86")]
+    unsafe {
+        __readfsdword(0x30) as usize
+    }
 
+    #[cfg(target_arch = "x86_64")]
     unsafe {
-        // Get process module information
-        let peb_ldr_data = *((__readfsdword(0x30) + 0xC) as *const usize);
+        __readgsqword(0x60) as usize
+    }
+}
 
-        // Get first module in initialization order
-        let mut it = *((peb_ldr_data + 0x1C) as *const *const LDR_DATA_TABLE_ENTRY);
+/// Whether our thread holds the loader lock, as it does in `DllMain`
+#[cfg(windows)]
+pub fn holds_loader_lock() -> bool {
+    let lock = unsafe { *((get_peb() + PEB_LOADER_LOCK_OFFSET) as *const PRTL_CRITICAL_SECTION) };
 
-        loop {
-            // Fail condition if not found
-            if it.is_null() || ((*it).BaseAddress as usize) == 0 {
-                // Iterator is invalid
-                break;
-            } else if lstrcmpiW((*it).BaseDllName.Buffer, wide_module) == 0 {
-                // We have a match
-                let start = (*it).BaseAddress as usize;
-                let end = start + (*it).SizeOfImage as usize;
+    !lock.is_null() && unsafe { RtlIsCriticalSectionLockedByThread(lock) } != 0
+}
 
-                return Ok(Module::new_with(start, end));
-            }
+/// Loader lock, released on drop
+#[cfg(windows)]
+struct LoaderLockGuard {
+    cookie: PVOID,
+}
 
-            // Advance iteration
-            it = (*it).InMemoryOrderModuleList.Flink as _;
-        }
+#[cfg(windows)]
+impl LoaderLockGuard {
+    /// Take the loader lock, so the loader list doesn't change under us
+    fn acquire() -> Option<Self> {
+        let mut cookie = null_mut();
+
+        NT_SUCCESS(unsafe { LdrLockLoaderLock(0, null_mut(), &mut cookie) })
+            .then_some(Self { cookie })
+    }
+}
+
+#[cfg(windows)]
+impl Drop for LoaderLockGuard {
+    fn drop(&mut self) {
+        unsafe { LdrUnlockLoaderLock(0, self.cookie) };
     }
+}
+
+/// Upper bound on loader entries we'll walk, guards against corrupted lists
+const MAX_LOADER_ENTRIES: usize = 4096;
+
+/// Turns `UNICODE_STRING` read from `source` to `String`, replacing invalid UTF-16
+fn read_unicode_string_from<S: MemorySource>(
+    source: &S,
+    string: &UNICODE_STRING,
+) -> ModuleErrOr<String> {
+    if string.Buffer == 0 || string.Length == 0 {
+        return Ok(String::new());
+    }
+
+    // `Length` is in bytes, without null terminator
+    let bytes = source.view(string.Buffer, string.Length as usize)?;
+    let wide = bytes
+        .chunks_exact(2)
+        .map(|x| u16::from_le_bytes([x[0], x[1]]))
+        .collect::<Vec<u16>>();
+
+    Ok(String::from_utf16_lossy(&wide))
+}
 
-    Err(Error::CantFind.into())
+/// Walk process' loaded modules, trying to match for our goal `module`
+#[cfg(windows)]
+fn get_module_range(module: &str) -> ModuleErrOr<Module> {
+    Module::enumerate()
+        .into_iter()
+        .find(|info| info.matches(module))
+        .map(|info| info.to_module())
+        .ok_or_else(|| Error::CantFind.into())
 }
 
-/// Get `base` module DOS header
-fn get_module_dos_header(base: usize) -> PIMAGE_DOS_HEADER {
-    base as _
+/// Offset of `e_lfanew` in `IMAGE_DOS_HEADER`
+const DOS_LFANEW_OFFSET: usize = 0x3C;
+
+/// `IMAGE_NT_OPTIONAL_HDR32_MAGIC`
+const OPTIONAL_HEADER_MAGIC_32: u16 = 0x10B;
+
+/// `IMAGE_NT_OPTIONAL_HDR64_MAGIC`
+const OPTIONAL_HEADER_MAGIC_64: u16 = 0x20B;
+
+/// Offset of `DataDirectory` in `IMAGE_OPTIONAL_HEADER32`
+const DATA_DIRECTORY_OFFSET_32: usize = 0x60;
+
+/// Offset of `DataDirectory` in `IMAGE_OPTIONAL_HEADER64`
+const DATA_DIRECTORY_OFFSET_64: usize = 0x70;
+
+/// `IMAGE_DIRECTORY_ENTRY_EXPORT`
+const DIRECTORY_ENTRY_EXPORT: usize = 0;
+
+/// `IMAGE_SCN_MEM_EXECUTE`
+const SECTION_MEM_EXECUTE: u32 = 0x20000000;
+
+/// PE file header
+#[derive(Clone, Copy)]
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[allow(non_snake_case)]
+#[allow(dead_code)]
+struct IMAGE_FILE_HEADER {
+    pub Machine: u16,
+    pub NumberOfSections: u16,
+    pub TimeDateStamp: u32,
+    pub PointerToSymbolTable: u32,
+    pub NumberOfSymbols: u32,
+    pub SizeOfOptionalHeader: u16,
+    pub Characteristics: u16,
 }
 
-/// Get `base` module NT headers
-fn get_module_nt_headers(base: usize) -> PIMAGE_NT_HEADERS {
-    unsafe { (base + (*get_module_dos_header(base)).e_lfanew as usize) as _ }
+/// PE data directory, RVA and size of a table
+#[derive(Clone, Copy)]
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[allow(non_snake_case)]
+#[allow(dead_code)]
+struct IMAGE_DATA_DIRECTORY {
+    pub VirtualAddress: u32,
+    pub Size: u32,
 }
 
-/// Get `base` module data directory `directory`
-fn get_module_data_directory(base: usize, directory: usize) -> usize {
-    let nt_headers = get_module_nt_headers(base);
-    unsafe { base + (*nt_headers).OptionalHeader.DataDirectory[directory].VirtualAddress as usize }
+/// PE export directory
+#[derive(Clone, Copy)]
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[allow(non_snake_case)]
+#[allow(dead_code)]
+struct IMAGE_EXPORT_DIRECTORY {
+    pub Characteristics: u32,
+    pub TimeDateStamp: u32,
+    pub MajorVersion: u16,
+    pub MinorVersion: u16,
+    pub Name: u32,
+    pub Base: u32,
+    pub NumberOfFunctions: u32,
+    pub NumberOfNames: u32,
+    pub AddressOfFunctions: u32,
+    pub AddressOfNames: u32,
+    pub AddressOfNameOrdinals: u32,
 }
 
-/// Get module exports for module at `base`
-fn get_module_exports(base: usize) -> Exports {
+/// PE section header, `Misc` is always the virtual size in images
+#[derive(Clone, Copy)]
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[allow(non_snake_case)]
+#[allow(dead_code)]
+struct IMAGE_SECTION_HEADER {
+    pub Name: [u8; 8],
+    pub VirtualSize: u32,
+    pub VirtualAddress: u32,
+    pub SizeOfRawData: u32,
+    pub PointerToRawData: u32,
+    pub PointerToRelocations: u32,
+    pub PointerToLinenumbers: u32,
+    pub NumberOfRelocations: u16,
+    pub NumberOfLinenumbers: u16,
+    pub Characteristics: u32,
+}
+
+// PE structures are laid out without padding
+
+unsafe impl Pod for IMAGE_FILE_HEADER {}
+unsafe impl Pod for IMAGE_DATA_DIRECTORY {}
+unsafe impl Pod for IMAGE_EXPORT_DIRECTORY {}
+unsafe impl Pod for IMAGE_SECTION_HEADER {}
+
+/// Read `T` at `offset` into `image`, if in bounds
+fn read_image<T: Pod>(image: &[u8], offset: usize) -> Option<T> {
+    image
+        .get(offset..offset.checked_add(size_of::<T>())?)
+        .map(|bytes| unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const T) })
+}
+
+/// Read null-terminated UTF-8 string at `offset` into `image`, if in bounds and not empty
+fn read_image_c_string(image: &[u8], offset: usize) -> Option<String> {
+    let bytes = image.get(offset..)?;
+    let length = bytes.iter().position(|&x| x == b'\0')?;
+
+    std::str::from_utf8(&bytes[..length])
+        .ok()
+        .filter(|x| !x.is_empty())
+        .map(str::to_owned)
+}
+
+/// Get offset of `IMAGE_FILE_HEADER` in `image`, right past the NT signature
+fn get_image_file_header_offset(image: &[u8]) -> Option<usize> {
+    (read_image::<u32>(image, DOS_LFANEW_OFFSET)? as usize).checked_add(size_of::<u32>())
+}
+
+/// Get `image` data directory `directory`, if present
+///
+/// The optional header is PE32 or PE32+ depending on the module's bitness,
+/// its magic tells which
+fn get_image_data_directory(image: &[u8], directory: usize) -> Option<IMAGE_DATA_DIRECTORY> {
+    let optional_header =
+        get_image_file_header_offset(image)?.checked_add(size_of::<IMAGE_FILE_HEADER>())?;
+    let data_directory = match read_image::<u16>(image, optional_header)? {
+        OPTIONAL_HEADER_MAGIC_32 => DATA_DIRECTORY_OFFSET_32,
+        OPTIONAL_HEADER_MAGIC_64 => DATA_DIRECTORY_OFFSET_64,
+        _ => return None,
+    };
+
+    // Directories past `NumberOfRvaAndSizes`, right before the table, aren't there
+    let count = read_image::<u32>(image, optional_header + data_directory - size_of::<u32>())?;
+    if directory >= count as usize {
+        return None;
+    }
+
+    read_image::<IMAGE_DATA_DIRECTORY>(
+        image,
+        optional_header + data_directory + directory * size_of::<IMAGE_DATA_DIRECTORY>(),
+    )
+    .filter(|x| x.VirtualAddress != 0)
+}
+
+/// Get module exports from mapped `image`, which lives at `base`
+fn get_module_exports(image: &[u8], base: usize) -> Exports {
     let mut result: Exports = Exports::new();
 
-    unsafe {
-        // Get EAT
-        let eat = get_module_data_directory(base, IMAGE_DIRECTORY_ENTRY_EXPORT.into())
-            as PIMAGE_EXPORT_DIRECTORY;
-
-        // Get tables
-        let name_table = (base + (*eat).AddressOfNames as usize) as *const usize;
-        let ordinal_table = (base + (*eat).AddressOfNameOrdinals as usize) as *const u16;
-        let function_table = (base + (*eat).AddressOfFunctions as usize) as *const usize;
-
-        let number_of_names = (*eat).NumberOfNames;
-        for i in 0..number_of_names as isize {
-            // Get n-th entry's name
-            let raw_name = (base + *name_table.offset(i)) as *mut c_schar;
-
-            // Retrieve C string in Rust
-            if let Some(entry_name) = read_c_string(raw_name as _) {
-                // Get export address
-                let export_address =
-                    base + *function_table.offset(*ordinal_table.offset(i) as isize);
-
-                // Verify if there's an offset from base, otherwise it's invalid
-                if export_address != base {
-                    // Add to hashmap
-                    result.insert(entry_name, GenericAddress::from(export_address));
-                }
-            }
+    // Get EAT, if module has exports at all
+    let Some(eat) = get_image_data_directory(image, DIRECTORY_ENTRY_EXPORT).and_then(|directory| {
+        read_image::<IMAGE_EXPORT_DIRECTORY>(image, directory.VirtualAddress as usize)
+    }) else {
+        return result;
+    };
+
+    // Get tables, entries are RVAs, which are 32-bit regardless of bitness
+    let name_table = eat.AddressOfNames as usize;
+    let ordinal_table = eat.AddressOfNameOrdinals as usize;
+    let function_table = eat.AddressOfFunctions as usize;
+
+    for i in 0..eat.NumberOfNames as usize {
+        // Get n-th entry's name
+        let entry_name = read_image::<u32>(image, name_table + i * size_of::<u32>())
+            .and_then(|rva| read_image_c_string(image, rva as usize));
+
+        // Get export RVA through its ordinal, verify if there's an offset from base,
+        // otherwise it's invalid
+        let function = read_image::<u16>(image, ordinal_table + i * size_of::<u16>())
+            .and_then(|ordinal| {
+                read_image::<u32>(image, function_table + ordinal as usize * size_of::<u32>())
+            })
+            .filter(|&function| function != 0);
+
+        if let (Some(entry_name), Some(function)) = (entry_name, function) {
+            // Add to hashmap
+            result.insert(entry_name, GenericAddress::from(base + function as usize));
         }
     }
 
     result
 }
 
+/// Get module sections from mapped `image`, which lives at `base`
+fn get_module_sections(image: &[u8], base: usize) -> Vec<Section> {
+    // Section headers follow the optional header, whatever size it claims to be
+    let Some(file_header_offset) = get_image_file_header_offset(image) else {
+        return Vec::new();
+    };
+    let Some(file_header) = read_image::<IMAGE_FILE_HEADER>(image, file_header_offset) else {
+        return Vec::new();
+    };
+
+    let table = file_header_offset
+        + size_of::<IMAGE_FILE_HEADER>()
+        + file_header.SizeOfOptionalHeader as usize;
+
+    (0..file_header.NumberOfSections as usize)
+        .map_while(|i| {
+            read_image::<IMAGE_SECTION_HEADER>(image, table + i * size_of::<IMAGE_SECTION_HEADER>())
+        })
+        .map(|header| {
+            // Name is padded with nulls, but not terminated if it takes all 8 bytes
+            let length = header
+                .Name
+                .iter()
+                .position(|&x| x == b'\0')
+                .unwrap_or(header.Name.len());
+
+            // Virtual size is what's mapped, some linkers leave it zero though
+            let size = match header.VirtualSize {
+                0 => header.SizeOfRawData,
+                size => size,
+            };
+
+            let start = base + header.VirtualAddress as usize;
+
+            Section {
+                name: String::from_utf8_lossy(&header.Name[..length]).into_owned(),
+                range: AddressRange::new(start, start + size as usize),
+                characteristics: header.Characteristics,
+            }
+        })
+        .collect()
+}
+
+#[cfg(windows)]
 impl Module {
-    /// Get module with name `module`, store bounds
+    /// Get module with name `module`, store bounds. `module` is either a base name
+    /// or a full path, matched case-insensitively (see `ModuleInfo::matches`)
     ///
     /// # Example
     ///
-    /// ```rust
-    /// let client = Module::new("client.dll\0").expect("Module not found in process module information");
+    /// ```ignore
+    /// let client = Module::new("client.dll").expect("Module not found in process module information");
     /// ```
     pub fn new(module: &str) -> ModuleErrOr<Self> {
         // Calls to new_with
         get_module_range(module)
     }
 
+    /// Get module with name `module`, waiting up to `timeout` for the loader to map it
+    ///
+    /// Wakes up on loader notifications when they're available, and polls
+    /// every `WAIT_POLL_INTERVAL` regardless. Under the loader lock (i.e. from
+    /// `DllMain`) the loader can't map anything, so it fails with `LoaderLock`
+    /// right away unless the module is already loaded.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let client = Module::wait_for("client.dll", Duration::from_secs(30))?;
+    /// ```
+    pub fn wait_for(module: &str, timeout: Duration) -> ModuleErrOr<Self> {
+        // Already loaded, don't bother with notifications
+        if let Ok(result) = Self::new(module) {
+            return Ok(result);
+        }
+
+        // Waiting would only block the loader, and with it the game
+        if holds_loader_lock() {
+            return Err(Error::LoaderLock.into());
+        }
+
+        let deadline = Instant::now() + timeout;
+
+        // Set whenever anything gets loaded
+        let signal = Arc::new((Mutex::new(false), Condvar::new()));
+        let _notification = {
+            let signal = signal.clone();
+            LoadNotification::register(move |_| {
+                let (loaded, condvar) = &*signal;
+                *loaded.lock().unwrap() = true;
+                condvar.notify_all();
+            })
+            .ok()
+        };
+
+        loop {
+            if let Ok(result) = Self::new(module) {
+                return Ok(result);
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::Timeout.into());
+            }
+
+            // Sleep till next load, or next poll
+            let (loaded, condvar) = &*signal;
+            let (mut loaded, _) = condvar
+                .wait_timeout_while(
+                    loaded.lock().unwrap(),
+                    (deadline - now).min(WAIT_POLL_INTERVAL),
+                    |loaded| !*loaded,
+                )
+                .unwrap();
+            *loaded = false;
+        }
+    }
+
+    /// Get information on all modules currently loaded in the process,
+    /// in initialization order
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// for info in Module::enumerate() {
+    ///     println!("{:#x} {}", info.get_base(), info.get_full_path());
+    /// }
+    /// ```
+    pub fn enumerate() -> Vec<ModuleInfo> {
+        // Entries get unlinked and freed as modules unload, hold the loader lock
+        // unless we already do (i.e. from `DllMain`)
+        let _lock = match holds_loader_lock() {
+            true => None,
+            false => LoaderLockGuard::acquire(),
+        };
+
+        // Our own loader list is always readable
+        Self::enumerate_from(&LocalMemory, get_peb()).unwrap_or_default()
+    }
+
+    /// Get information on loaded module containing `address`
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // Verify a vtable entry points into client.dll
+    /// let owner = Module::find_containing(function.exposed_addr())?;
+    /// assert!(owner.get_base_name().eq_ignore_ascii_case("client.dll"));
+    /// ```
+    pub fn find_containing(address: usize) -> ModuleErrOr<ModuleInfo> {
+        Self::enumerate()
+            .into_iter()
+            .find(|info| info.contains(address))
+            .ok_or_else(|| Error::CantFind.into())
+    }
+
     /// Get module which you guarantee to be within `start` and `end`
     pub fn new_with(start: usize, end: usize) -> Self {
+        Self::new_with_source(LocalMemory, start, end)
+    }
+}
+
+impl<S: MemorySource + Clone> Module<S> {
+    /// Get module which you guarantee to be within `start` and `end` of `source`
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let process = ProcessMemory::open(process_id)?;
+    /// let client = Module::new_with_source(process, info.get_base(), info.get_end());
+    /// ```
+    pub fn new_with_source(source: S, start: usize, end: usize) -> Self {
+        let range = AddressRange::new(start, end);
+
+        // Unreadable image just means no exports nor sections
+        let (exports, sections) = source
+            .view(range.get_start(), range.get_size())
+            .map(|image| {
+                (
+                    get_module_exports(&image, start),
+                    get_module_sections(&image, start),
+                )
+            })
+            .unwrap_or_default();
+
         Self {
-            start,
-            end,
-            exports: get_module_exports(start),
+            source,
+            range,
+            exports,
+            sections,
+        }
+    }
+
+    /// Get information on all modules in the loader list of the process whose
+    /// PEB is at `peb`, read through `source`, in initialization order
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let process = ProcessMemory::open(process_id)?;
+    /// let modules = Module::enumerate_from(&process, process.get_peb()?)?;
+    /// ```
+    pub fn enumerate_from(source: &S, peb: usize) -> ModuleErrOr<Vec<ModuleInfo>> {
+        let mut result = Vec::new();
+
+        // Get process module information
+        let peb_ldr_data = source.read::<usize>(peb + PEB_LDR_OFFSET)?;
+
+        // Get first module in initialization order, list is circular through its head
+        let head = peb_ldr_data + LDR_INITIALIZATION_ORDER_OFFSET;
+        let mut it = source.read::<usize>(head)?;
+
+        while it != 0 && it != head && result.len() < MAX_LOADER_ENTRIES {
+            // Plain pointers and integers, only padded on x64
+            let entry = unsafe { source.read_unchecked::<LDR_DATA_TABLE_ENTRY>(it)? };
+            if entry.BaseAddress == 0 {
+                break;
+            }
+
+            result.push(ModuleInfo::new(
+                entry.BaseAddress,
+                entry.SizeOfImage as usize,
+                read_unicode_string_from(source, &entry.FullDllName)?,
+                read_unicode_string_from(source, &entry.BaseDllName)?,
+            ));
+
+            // Advance iteration
+            it = entry.InMemoryOrderModuleList.Flink;
         }
+
+        Ok(result)
+    }
+
+    /// Get memory source module is read through
+    pub fn get_source(&self) -> &S {
+        &self.source
+    }
+
+    /// Get addresses module spans
+    pub fn get_range(&self) -> AddressRange {
+        self.range
     }
 
     /// Get module base address
     pub fn get_start(&self) -> usize {
-        self.start
+        self.range.get_start()
     }
 
     /// Get module end address
     pub fn get_end(&self) -> usize {
-        self.end
+        self.range.get_end()
     }
 
     /// Get module size
     pub fn get_size(&self) -> usize {
-        self.end - self.start
+        self.range.get_size()
+    }
+
+    /// Whether `address` lies within module bounds
+    pub fn contains(&self, address: usize) -> bool {
+        self.range.contains(address)
     }
 
     /// Get module exports address
@@ -198,97 +690,120 @@ impl Module {
         &self.exports
     }
 
-    /// Internal implementation for pattern finding methods
+    /// Get module sections, in header order
+    pub fn get_sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// Get section named `name` (e.g. `.text`)
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let text = client.get_section(".text")?.get_range();
+    /// let result = client.find_pattern_in(text, "55 8b ec")?;
+    /// ```
+    pub fn get_section(&self, name: &str) -> ModuleErrOr<&Section> {
+        self.sections
+            .iter()
+            .find(|section| section.name == name)
+            .ok_or_else(|| Error::CantFind.into())
+    }
+
+    /// Internal implementation for pattern finding methods, scans the part
+    /// of `range` that lies in the module
     fn find_nth_pattern_bytes_impl(
         &self,
+        range: AddressRange,
         pattern: &[u8],
-        current_match: usize,
         goal_match: usize,
     ) -> ModuleErrOr<GenericAddress> {
-        // Get slice in memory
-        let slice = unsafe {
-            std::slice::from_raw_parts(self.start as *const u8, self.get_size() - pattern.len())
-        };
+        let range = self.range.intersect(&range).ok_or(Error::CantFind)?;
 
-        /// Needle-in-haystack predicate which acknowledges wildcard
-        fn needle_in_haystack(view: &[u8], pattern: &[u8]) -> bool {
-            // True if there's no false entry in map (full match)
-            !view
-                .iter()
-                .zip(pattern)
-                .map(|(&view_entry, &pattern_entry)| {
-                    view_entry == pattern_entry || pattern_entry == 0xCC
-                })
-                .any(|x| !x)
-        }
+        // Get slice in memory, all of it, matches can end right at the end
+        let slice = self.source.view(range.get_start(), range.get_size())?;
 
-        // Perform needle-and-haystack on `pattern.len()` windows to view.
-        // If succesful, pad iterator by base address.
-        // Recurses, need be.
-        slice
-            .windows(pattern.len())
-            .position(|x| needle_in_haystack(x, pattern))
+        find_nth_pattern_in_slice(&slice, pattern, goal_match)
+            .map(|x| GenericAddress::from(range.get_start() + x))
             .ok_or_else(|| Error::CantFind.into())
-            .and_then(|x| {
-                if current_match != goal_match {
-                    // Partially copy current module (without exports)
-                    // This would be less of a code smell if modules were called ranges,
-                    // But that implies way more overhead in abstraction
-                    let next = Self {
-                        // `+ 1` makes position not fall on the same window
-                        start: self.start + x + 1,
-                        end: self.end,
-                        exports: Exports::default(),
-                    };
-
-                    // Recur till you get to goal
-                    next.find_nth_pattern_bytes_impl(pattern, current_match + 1, goal_match)
-                } else {
-                    Ok(GenericAddress::from(self.start + x))
-                }
-            })
     }
 
-    /// Find `GOAL`-th instance of `pattern` in `{self.start, self.end}` slice
+    /// Find `GOAL`-th instance of `pattern` in the part of `range` that lies in module
     ///
     /// # Examples
     ///
-    /// ```rust
+    /// ```ignore
+    /// // Gets second instance of pattern (starts at 0) in .text
+    /// let text = client.get_section(".text")?.get_range();
+    /// let result = client.find_nth_pattern_bytes_in(text, &[0x55u8, 0x8b, 0xec], 1)?;
+    /// ```
+    pub fn find_nth_pattern_bytes_in(
+        &self,
+        range: AddressRange,
+        pattern: &[u8],
+        goal_match: usize,
+    ) -> ModuleErrOr<GenericAddress> {
+        self.find_nth_pattern_bytes_impl(range, pattern, goal_match)
+    }
+
+    /// Find `GOAL`-th instance of `pattern` in module
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
     /// // Gets second instance of pattern (starts at 0)
-    /// let result = Module::new("client.dll\0").find_nth_pattern_bytes(&[0x55u8, 0x8b, 0xec], 1)?;
+    /// let result = Module::new("client.dll").find_nth_pattern_bytes(&[0x55u8, 0x8b, 0xec], 1)?;
     /// ```
     pub fn find_nth_pattern_bytes(
         &self,
         pattern: &[u8],
         goal_match: usize,
     ) -> ModuleErrOr<GenericAddress> {
-        self.find_nth_pattern_bytes_impl(pattern, 0, goal_match)
+        self.find_nth_pattern_bytes_in(self.range, pattern, goal_match)
     }
 
-    /// Find first instance of `pattern` in `{self.start, self.end}` slice
+    /// Find first instance of `pattern` in module
     ///
     /// # Examples
     ///
-    /// ```rust
+    /// ```ignore
     /// // Gets second instance of pattern (starts at 0)
-    /// let result = Module::new("client.dll\0").find_pattern_bytes(&[0x55u8, 0x8b, 0xec])?;
+    /// let result = Module::new("client.dll").find_pattern_bytes(&[0x55u8, 0x8b, 0xec])?;
     /// ```
     pub fn find_pattern_bytes(&self, pattern: &[u8]) -> ModuleErrOr<GenericAddress> {
         self.find_nth_pattern_bytes(pattern, 0)
     }
 
-    /// Find `GOAL`-th instance of `pattern` in `{self.start, self.end}` slice
+    /// Find `GOAL`-th instance of `pattern` in module
     ///
     /// # Examples
     ///
-    /// ```rust
+    /// ```ignore
     /// // Gets second instance of pattern (starts at 0)
-    /// let result = Module::new("client.dll\0").find_nth_pattern("55 8b ec", 1)?;
+    /// let result = Module::new("client.dll").find_nth_pattern("55 8b ec", 1)?;
     /// ```
     pub fn find_nth_pattern(
         &self,
         pattern: &str,
         goal_match: usize,
+    ) -> ModuleErrOr<GenericAddress> {
+        self.find_nth_pattern_in(self.range, pattern, goal_match)
+    }
+
+    /// Find `GOAL`-th instance of `pattern` in the part of `range` that lies in module
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Gets first instance of pattern within a function
+    /// let function = AddressRange::with_size(function_start, 0x100)?;
+    /// let result = client.find_nth_pattern_in(function, "8b 0d ? ? ? ?", 0)?;
+    /// ```
+    pub fn find_nth_pattern_in(
+        &self,
+        range: AddressRange,
+        pattern: &str,
+        goal_match: usize,
     ) -> ModuleErrOr<GenericAddress> {
         /// Hex pattern string to `Vec<u8>`
         fn string_to_array(pattern: &str) -> Vec<u8> {
@@ -296,7 +811,7 @@ impl Module {
                 .split_whitespace()
                 .map(|x| {
                     if x.contains('?') {
-                        0xCC
+                        PATTERN_WILDCARD
                     } else {
                         u8::from_str_radix(x, 16).unwrap()
                     }
@@ -304,28 +819,65 @@ impl Module {
                 .collect::<Vec<u8>>()
         }
 
-        self.find_nth_pattern_bytes(string_to_array(pattern).as_slice(), goal_match)
+        self.find_nth_pattern_bytes_in(range, string_to_array(pattern).as_slice(), goal_match)
     }
 
-    /// Find first instance of `pattern` in `{self.start, self.end}` slice
+    /// Find first instance of `pattern` in module
     ///
     /// # Examples
     ///
-    /// ```rust
+    /// ```ignore
     /// // Gets second instance of pattern (starts at 0)
-    /// let result = Module::new("client.dll\0").find_pattern("55 8b ec")?;
+    /// let result = Module::new("client.dll").find_pattern("55 8b ec")?;
     /// ```
     pub fn find_pattern(&self, pattern: &str) -> ModuleErrOr<GenericAddress> {
         self.find_nth_pattern(pattern, 0)
     }
 
-    /// Find first instance of C ABI `string` reference in `{self.start, self.end}` slice
+    /// Find first instance of `pattern` in the part of `range` that lies in module
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let text = client.get_section(".text")?.get_range();
+    /// let result = client.find_pattern_in(text, "55 8b ec")?;
+    /// ```
+    pub fn find_pattern_in(
+        &self,
+        range: AddressRange,
+        pattern: &str,
+    ) -> ModuleErrOr<GenericAddress> {
+        self.find_nth_pattern_in(range, pattern, 0)
+    }
+
+    /// Find first instance of C ABI `string` reference in module
     ///
     /// # Examples
-    /// ```rust
-    /// let reference = Module::new("client.dll\0")?.find_nth_string("CViewRender::SetUpView->OnRenderEnd", 0)?;
+    /// ```ignore
+    /// let reference = Module::new("client.dll")?.find_nth_string("CViewRender::SetUpView->OnRenderEnd", 0)?;
     /// ```
     pub fn find_nth_string(&self, string: &str, goal_match: usize) -> ModuleErrOr<GenericAddress> {
+        self.find_nth_string_in(self.range, string, goal_match)
+    }
+
+    /// Find `GOAL`-th reference to C ABI `string` in the part of `range` that lies
+    /// in module, `string` itself can be anywhere in module
+    ///
+    /// Gets the address of the reference's operand: the absolute address on x86,
+    /// the displacement of `lea reg, [rip + disp32]` on x86-64, where code never
+    /// holds absolute addresses.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let text = client.get_section(".text")?.get_range();
+    /// let reference = client.find_nth_string_in(text, "CViewRender::SetUpView->OnRenderEnd", 0)?;
+    /// ```
+    pub fn find_nth_string_in(
+        &self,
+        range: AddressRange,
+        string: &str,
+        goal_match: usize,
+    ) -> ModuleErrOr<GenericAddress> {
         let pattern = string
             .chars()
             .chain(Some('\0')) // add null terminator
@@ -333,22 +885,305 @@ impl Module {
             .collect::<Vec<u8>>();
 
         // Get address of string in rdata to search for as xref
-        let string_address = self.find_pattern_bytes(pattern.as_ref())?;
+        let string_address = self.find_pattern_bytes(pattern.as_ref())?.exposed_addr();
+
+        match Mode::native() {
+            // Turn string address to little endianness order bytes and
+            // overshadow pattern with it
+            Mode::Bits32 => self.find_nth_pattern_bytes_in(
+                range,
+                &(string_address as u32).to_le_bytes(),
+                goal_match,
+            ),
+            Mode::Bits64 => self.find_nth_rip_relative_lea_in(range, string_address, goal_match),
+        }
+    }
 
-        // Turn string address to little endianness order bytes and
-        // overshadow pattern with it
-        let pattern = string_address.exposed_addr().to_le_bytes();
+    /// Find `GOAL`-th `lea reg, [rip + disp32]` loading `target` in the part of
+    /// `range` that lies in module, gets the address of its displacement
+    fn find_nth_rip_relative_lea_in(
+        &self,
+        range: AddressRange,
+        target: usize,
+        goal_match: usize,
+    ) -> ModuleErrOr<GenericAddress> {
+        /// REX prefix, opcode, ModRM and displacement
+        const LEA_LENGTH: usize = 7;
 
-        self.find_nth_pattern_bytes(pattern.as_ref(), goal_match)
+        let range = self.range.intersect(&range).ok_or(Error::CantFind)?;
+        let slice = self.source.view(range.get_start(), range.get_size())?;
+
+        slice
+            .windows(LEA_LENGTH)
+            .enumerate()
+            // Any REX prefix, `mod` 00 and `r/m` 101 being RIP-relative
+            .filter(|(_, x)| x[0] & 0xF0 == 0x40 && x[1] == 0x8D && x[2] & 0xC7 == 0x05)
+            .filter(|(i, x)| {
+                let displacement = i32::from_le_bytes([x[3], x[4], x[5], x[6]]);
+
+                (range.get_start() + i + LEA_LENGTH).checked_add_signed(displacement as isize)
+                    == Some(target)
+            })
+            .nth(goal_match)
+            .map(|(i, _)| GenericAddress::from(range.get_start() + i + 3))
+            .ok_or_else(|| Error::CantFind.into())
     }
 
-    /// Find first instance of C ABI `string` reference in `{self.start, self.end}` slice
+    /// Find first instance of C ABI `string` reference in module
     ///
     /// # Examples
-    /// ```rust
-    /// let reference = Module::new("client.dll\0")?.find_string("CViewRender::SetUpView->OnRenderEnd")?;
+    /// ```ignore
+    /// let reference = Module::new("client.dll")?.find_string("CViewRender::SetUpView->OnRenderEnd")?;
     /// ```
     pub fn find_string(&self, string: &str) -> ModuleErrOr<GenericAddress> {
         self.find_nth_string(string, 0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::{
+        pointer_path::PointerPath,
+        snapshot::{Snapshot, SnapshotMemory},
+        source::{BufferMemory, Protection},
+    };
+
+    const BASE: usize = 0x10000;
+    const IMAGE_SIZE: usize = 0x600;
+    const HEAP: usize = BASE + IMAGE_SIZE;
+    const HEAP_SIZE: usize = 0x100;
+
+    /// Name, RVA, size and characteristics of every section
+    const SECTIONS: [(&str, usize, usize, u32); 3] = [
+        (".text", 0x200, 0x100, 0x60000020),
+        (".rdata", 0x300, 0x200, 0x40000040),
+        (".data", 0x500, 0x100, 0xC0000040),
+    ];
+
+    const STRING: &str = "CViewRender::SetUpView->OnRenderEnd";
+
+    fn put(bytes: &mut [u8], offset: usize, value: &[u8]) {
+        bytes[offset..offset + value.len()].copy_from_slice(value);
+    }
+
+    /// Headers of an image as wide as we are, exporting `CreateInterface` at
+    /// `.text`'s start, followed by a page of heap
+    fn image() -> Vec<u8> {
+        let mut bytes = vec![0; IMAGE_SIZE + HEAP_SIZE];
+        let (machine, magic, data_directory, optional_size): (u16, u16, usize, u16) =
+            if size_of::<usize>() == 8 {
+                (0x8664, 0x20B, 0x70, 0xF0)
+            } else {
+                (0x14C, 0x10B, 0x60, 0xE0)
+            };
+
+        put(&mut bytes, 0, b"MZ");
+        put(&mut bytes, 0x3C, &0x80u32.to_le_bytes());
+        put(&mut bytes, 0x80, b"PE\0\0");
+        put(&mut bytes, 0x84, &machine.to_le_bytes());
+        put(&mut bytes, 0x86, &(SECTIONS.len() as u16).to_le_bytes());
+        put(&mut bytes, 0x94, &optional_size.to_le_bytes());
+
+        // Only the export directory is there
+        let optional = 0x98;
+        put(&mut bytes, optional, &magic.to_le_bytes());
+        put(
+            &mut bytes,
+            optional + data_directory - 4,
+            &16u32.to_le_bytes(),
+        );
+        put(
+            &mut bytes,
+            optional + data_directory,
+            &0x400u32.to_le_bytes(),
+        );
+        put(
+            &mut bytes,
+            optional + data_directory + 4,
+            &0x60u32.to_le_bytes(),
+        );
+
+        let table = optional + optional_size as usize;
+        for (i, (name, rva, size, characteristics)) in SECTIONS.into_iter().enumerate() {
+            let header = table + i * size_of::<IMAGE_SECTION_HEADER>();
+            put(&mut bytes, header, name.as_bytes());
+            put(&mut bytes, header + 8, &(size as u32).to_le_bytes());
+            put(&mut bytes, header + 12, &(rva as u32).to_le_bytes());
+            put(&mut bytes, header + 36, &characteristics.to_le_bytes());
+        }
+
+        // Export directory, one function, one name
+        put(&mut bytes, 0x414, &1u32.to_le_bytes());
+        put(&mut bytes, 0x418, &1u32.to_le_bytes());
+        put(&mut bytes, 0x41C, &0x440u32.to_le_bytes());
+        put(&mut bytes, 0x420, &0x444u32.to_le_bytes());
+        put(&mut bytes, 0x424, &0x448u32.to_le_bytes());
+        put(&mut bytes, 0x440, &0x200u32.to_le_bytes());
+        put(&mut bytes, 0x444, &0x450u32.to_le_bytes());
+        put(&mut bytes, 0x450, b"CreateInterface\0");
+
+        bytes
+    }
+
+    /// Module replayed from a snapshot of `bytes`, mapped at `BASE`
+    fn module(bytes: Vec<u8>) -> Module<SnapshotMemory> {
+        let memory = BufferMemory::new(BASE, bytes, Protection::READ_WRITE_EXECUTE);
+        let info = ModuleInfo::new(
+            BASE,
+            IMAGE_SIZE,
+            "C:\\tf\\bin\\client.dll".to_owned(),
+            "client.dll".to_owned(),
+        );
+
+        let snapshot =
+            Snapshot::capture(&memory, vec![info.clone()], &[(HEAP, HEAP_SIZE)]).unwrap();
+        info.to_module_with(SnapshotMemory::try_from(snapshot).unwrap())
+    }
+
+    #[test]
+    fn exports_and_sections() {
+        let module = module(image());
+
+        assert_eq!(module.get_exports().len(), 1);
+        assert_eq!(
+            module.get_exports()["CreateInterface"].exposed_addr(),
+            BASE + 0x200
+        );
+
+        let names = module
+            .get_sections()
+            .iter()
+            .map(Section::get_name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, [".text", ".rdata", ".data"]);
+
+        let text = module.get_section(".text").unwrap();
+        assert_eq!(
+            text.get_range(),
+            AddressRange::new(BASE + 0x200, BASE + 0x300)
+        );
+        assert!(text.is_executable());
+        assert!(!module.get_section(".data").unwrap().is_executable());
+        assert!(module.get_section(".reloc").is_err());
+    }
+
+    #[test]
+    fn garbage_headers() {
+        // No exports nor sections, but scans still work
+        let mut bytes = vec![0xFF; IMAGE_SIZE + HEAP_SIZE];
+        put(&mut bytes, 0x100, &[0x55, 0x8B, 0xEC]);
+        let memory = BufferMemory::new(BASE, bytes, Protection::READ);
+        let module = Module::new_with_source(memory, BASE, BASE + IMAGE_SIZE);
+
+        assert!(module.get_exports().is_empty());
+        assert!(module.get_sections().is_empty());
+        assert_eq!(
+            module.find_pattern("55 8b ec").unwrap().exposed_addr(),
+            BASE + 0x100
+        );
+    }
+
+    #[test]
+    fn pattern_scans() {
+        let mut bytes = image();
+        put(&mut bytes, 0x200, &[0x55, 0x8B, 0xEC]);
+        put(
+            &mut bytes,
+            0x210,
+            &[0x8B, 0x0D, 0x00, 0x05, 0x01, 0x00, 0x8B, 0x01],
+        );
+        put(&mut bytes, 0x280, &[0x55, 0x8B, 0xEC]);
+        let module = module(bytes);
+
+        let find = |pattern, goal_match| {
+            module
+                .find_nth_pattern(pattern, goal_match)
+                .map(|x| x.exposed_addr())
+        };
+        assert_eq!(find("55 8b ec", 0).unwrap(), BASE + 0x200);
+        assert_eq!(find("55 8b ec", 1).unwrap(), BASE + 0x280);
+        assert!(find("55 8b ec", 2).is_err());
+        assert_eq!(find("8B 0D ? ? ? ? 8B 01", 0).unwrap(), BASE + 0x210);
+        assert!(find("8B 0D ? ? ? ? 8B 02", 0).is_err());
+
+        // Ranges are clamped to the module, and searched from their start
+        let text = module.get_section(".text").unwrap().get_range();
+        let after = AddressRange::new(BASE + 0x201, text.get_end());
+        assert_eq!(
+            module
+                .find_pattern_in(after, "55 8b ec")
+                .unwrap()
+                .exposed_addr(),
+            BASE + 0x280
+        );
+        let past = AddressRange::with_size(BASE + IMAGE_SIZE, HEAP_SIZE).unwrap();
+        assert!(module.find_pattern_in(past, "00").is_err());
+
+        assert_eq!(
+            module
+                .find_pattern_bytes(&[0x8B, 0x0D, PATTERN_WILDCARD, 0x05])
+                .unwrap()
+                .exposed_addr(),
+            BASE + 0x210
+        );
+    }
+
+    #[test]
+    fn string_scans() {
+        let mut bytes = image();
+        put(&mut bytes, 0x300, STRING.as_bytes());
+
+        // Referenced the way the compiler would for our bitness
+        let operand = match Mode::native() {
+            // `push offset STRING`
+            Mode::Bits32 => {
+                put(&mut bytes, 0x220, &[0x68]);
+                put(&mut bytes, 0x221, &(BASE as u32 + 0x300).to_le_bytes());
+                0x221
+            }
+            // `lea rcx, [rip + STRING]`, and the same bytes pointing elsewhere
+            Mode::Bits64 => {
+                put(&mut bytes, 0x220, &[0x48, 0x8D, 0x0D]);
+                put(&mut bytes, 0x223, &(0x300i32 - 0x227).to_le_bytes());
+                put(&mut bytes, 0x210, &[0x48, 0x8D, 0x0D]);
+                put(&mut bytes, 0x213, &(0x300i32 - 0x210).to_le_bytes());
+                0x223
+            }
+        };
+        let module = module(bytes);
+
+        assert_eq!(
+            module.find_string(STRING).unwrap().exposed_addr(),
+            BASE + operand
+        );
+        assert!(module.find_nth_string(STRING, 1).is_err());
+
+        // Prefixes aren't the string, it's matched with its terminator
+        assert!(module.find_string("CViewRender::SetUpView").is_err());
+    }
+
+    #[test]
+    fn pointer_paths() {
+        let mut bytes = image();
+
+        // `.data` global -> heap object -> pointer to its member
+        put(&mut bytes, 0x500, &HEAP.to_le_bytes());
+        put(&mut bytes, IMAGE_SIZE + 0x10, &(HEAP + 0x80).to_le_bytes());
+        put(&mut bytes, IMAGE_SIZE + 0x84, &1337i32.to_le_bytes());
+        let module = module(bytes);
+
+        let path = PointerPath::<i32>::new(BASE + 0x500, &[0x10, 0x4]);
+        assert_eq!(path.read_from(module.get_source()).unwrap(), 1337);
+        assert_eq!(
+            path.resolve_from(module.get_source())
+                .unwrap()
+                .exposed_addr(),
+            HEAP + 0x84
+        );
+
+        // Past the captured heap
+        let path = PointerPath::<i32>::new(BASE + 0x500, &[HEAP_SIZE as isize]);
+        assert!(path.read_from(module.get_source()).is_err());
+    }
+}
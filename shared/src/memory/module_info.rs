@@ -0,0 +1,129 @@
+//! Module info module
+//!
+//! What the loader list says about a module, kept apart from `Module` so it's
+//! usable without Windows, by snapshots and region walks.
+
+use crate::{
+    error::Error,
+    memory::{range::AddressRange, source::MemorySource},
+    GenericErrOr,
+};
+
+/// Type for module info errors
+pub type ModuleInfoErrOr<T> = GenericErrOr<T>;
+
+/// Offset of `e_lfanew` in `IMAGE_DOS_HEADER`
+const DOS_LFANEW_OFFSET: usize = 0x3C;
+
+/// Offset of `FileHeader.Machine` in `IMAGE_NT_HEADERS`, right past the signature
+const NT_MACHINE_OFFSET: usize = 4;
+
+/// `IMAGE_FILE_MACHINE_I386`
+const MACHINE_I386: u16 = 0x14C;
+
+/// `IMAGE_FILE_MACHINE_AMD64`
+const MACHINE_AMD64: u16 = 0x8664;
+
+/// Loaded module information, as registered in the process' loader list
+#[derive(Debug, Clone)]
+pub struct ModuleInfo {
+    base: usize,
+    size: usize,
+    full_path: String,
+    base_name: String,
+}
+
+impl ModuleInfo {
+    /// Describe module of `size` bytes mapped at `base`, as the loader would
+    pub fn new(base: usize, size: usize, full_path: String, base_name: String) -> Self {
+        Self {
+            base,
+            size,
+            full_path,
+            base_name,
+        }
+    }
+
+    /// Get module base address
+    pub fn get_base(&self) -> usize {
+        self.base
+    }
+
+    /// Get module size
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    /// Get module end address
+    pub fn get_end(&self) -> usize {
+        self.base + self.size
+    }
+
+    /// Get addresses module image spans
+    pub fn get_range(&self) -> AddressRange {
+        AddressRange::new(self.base, self.get_end())
+    }
+
+    /// Get module full path (e.g. `C:\...\tf\bin\client.dll`)
+    pub fn get_full_path(&self) -> &str {
+        &self.full_path
+    }
+
+    /// Get module base name (e.g. `client.dll`)
+    pub fn get_base_name(&self) -> &str {
+        &self.base_name
+    }
+
+    /// Whether `address` lies within module image
+    pub fn contains(&self, address: usize) -> bool {
+        (self.base..self.get_end()).contains(&address)
+    }
+
+    /// Whether `module` refers to this module, case-insensitive. Compared against
+    /// full path if `module` has path separators, against base name otherwise
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use shared::memory::module_info::ModuleInfo;
+    /// # let info = ModuleInfo::new(
+    /// #     0x10000000,
+    /// #     0x1000,
+    /// #     "C:\\Program Files (x86)\\Steam\\steamapps\\common\\Team Fortress 2\\tf\\bin\\client.dll".to_owned(),
+    /// #     "client.dll".to_owned(),
+    /// # );
+    /// assert!(info.matches("CLIENT.dll"));
+    /// assert!(info.matches("C:/Program Files (x86)/Steam/steamapps/common/Team Fortress 2/tf/bin/client.dll"));
+    /// ```
+    pub fn matches(&self, module: &str) -> bool {
+        let name = if module.contains(['\\', '/']) {
+            &self.full_path
+        } else {
+            &self.base_name
+        };
+
+        normalize_module_name(name) == normalize_module_name(module)
+    }
+
+    /// Get size of pointers module was built for, in bytes, from the machine
+    /// its PE header names, read through `source`
+    pub fn read_pointer_width<S: MemorySource>(&self, source: &S) -> ModuleInfoErrOr<usize> {
+        let nt_headers = source.read::<u32>(self.base + DOS_LFANEW_OFFSET)? as usize;
+
+        match source.read::<u16>(self.base + nt_headers + NT_MACHINE_OFFSET)? {
+            MACHINE_I386 => Ok(4),
+            MACHINE_AMD64 => Ok(8),
+            _ => Err(Error::CantFind.into()),
+        }
+    }
+}
+
+/// Normalize `name` for comparison the way the loader does, case-insensitive,
+/// separator-agnostic, and ignoring a trailing null terminator
+fn normalize_module_name(name: &str) -> String {
+    name.trim_end_matches('\0')
+        .chars()
+        .map(|x| if x == '/' { '\\' } else { x })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
@@ -0,0 +1,109 @@
+//! Loader notification module
+
+use crate::{memory::module_info::ModuleInfo, GenericErrOr};
+use ntapi::{
+    ntldr::{
+        LdrRegisterDllNotification, LdrUnregisterDllNotification, LDR_DLL_LOADED_NOTIFICATION_DATA,
+        LDR_DLL_NOTIFICATION_REASON_LOADED, PLDR_DLL_NOTIFICATION_DATA,
+    },
+    ntrtl::RtlNtStatusToDosError,
+};
+use std::{io, ptr};
+use winapi::shared::ntdef::{NT_SUCCESS, PVOID, ULONG, UNICODE_STRING};
+
+/// Type for loader notification errors
+pub type NotificationErrOr<T> = GenericErrOr<T>;
+
+/// Callback type for module loads
+type LoadCallback = Box<dyn Fn(&ModuleInfo) + Send + Sync>;
+
+/// Registration for module load notifications, calls back for every module
+/// the loader maps until dropped
+///
+/// The callback runs under the loader lock, so keep it short, and don't load
+/// libraries or wait on other threads from it.
+pub struct LoadNotification {
+    cookie: PVOID,
+    /// Boxed twice so we can hand a thin pointer to the loader
+    callback: *mut LoadCallback,
+}
+
+// Cookie is only ever passed back to the loader, callback is `Send + Sync`
+unsafe impl Send for LoadNotification {}
+unsafe impl Sync for LoadNotification {}
+
+/// Turns `UNICODE_STRING` pointer to `String`, if not null, replacing invalid UTF-16
+unsafe fn read_unicode_string_ptr(string: *const UNICODE_STRING) -> String {
+    if string.is_null() || (*string).Buffer.is_null() {
+        return String::new();
+    }
+
+    // `Length` is in bytes, without null terminator
+    let wide = std::slice::from_raw_parts((*string).Buffer, (*string).Length as usize / 2);
+    String::from_utf16_lossy(wide)
+}
+
+/// Trampoline the loader calls, forwards loads to our callback
+unsafe extern "system" fn notification_trampoline(
+    reason: ULONG,
+    data: PLDR_DLL_NOTIFICATION_DATA,
+    context: PVOID,
+) {
+    if reason != LDR_DLL_NOTIFICATION_REASON_LOADED || data.is_null() || context.is_null() {
+        return;
+    }
+
+    // Loaded and unloaded variants share layout, so we can read through either
+    let loaded = &*(data as *const LDR_DLL_LOADED_NOTIFICATION_DATA);
+    let info = ModuleInfo::new(
+        loaded.DllBase as usize,
+        loaded.SizeOfImage as usize,
+        read_unicode_string_ptr(loaded.FullDllName),
+        read_unicode_string_ptr(loaded.BaseDllName),
+    );
+
+    (*(context as *const LoadCallback))(&info);
+}
+
+impl LoadNotification {
+    /// Register `callback` to be called for every module loaded from now on
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let _notification = LoadNotification::register(|info| {
+    ///     println!("Loaded {} at {:#x}", info.get_base_name(), info.get_base());
+    /// })?;
+    /// ```
+    pub fn register(
+        callback: impl Fn(&ModuleInfo) + Send + Sync + 'static,
+    ) -> NotificationErrOr<Self> {
+        let callback: LoadCallback = Box::new(callback);
+        let callback = Box::into_raw(Box::new(callback));
+        let mut cookie = ptr::null_mut();
+
+        let status = unsafe {
+            LdrRegisterDllNotification(0, Some(notification_trampoline), callback as _, &mut cookie)
+        };
+
+        if !NT_SUCCESS(status) {
+            // Loader never saw it, reclaim
+            drop(unsafe { Box::from_raw(callback) });
+            return Err(
+                io::Error::from_raw_os_error(unsafe { RtlNtStatusToDosError(status) } as _).into(),
+            );
+        }
+
+        Ok(Self { cookie, callback })
+    }
+}
+
+impl Drop for LoadNotification {
+    fn drop(&mut self) {
+        unsafe {
+            // Loader guarantees no callbacks are in flight once this returns
+            LdrUnregisterDllNotification(self.cookie);
+            drop(Box::from_raw(self.callback));
+        }
+    }
+}
@@ -0,0 +1,413 @@
+//! Patch module
+
+#[cfg(windows)]
+use crate::memory::source::LocalMemory;
+use crate::{
+    error::{Error, PatchError},
+    memory::{range::AddressRange, source::MemorySource},
+    GenericErrOr,
+};
+
+/// Type for patch errors
+pub type PatchErrOr<T> = GenericErrOr<T>;
+
+/// Applied byte patch
+#[derive(Debug, Clone)]
+pub struct Patch {
+    name: String,
+    range: AddressRange,
+    original: Vec<u8>,
+    patched: Vec<u8>,
+}
+
+impl Patch {
+    /// Get name patch was applied under
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get addresses patch covers
+    pub fn get_range(&self) -> AddressRange {
+        self.range
+    }
+
+    /// Get bytes that were there before patching
+    pub fn get_original(&self) -> &[u8] {
+        &self.original
+    }
+
+    /// Get bytes patch wrote
+    pub fn get_patched(&self) -> &[u8] {
+        &self.patched
+    }
+}
+
+/// Whether `found` is `expected`, where `None` matches anything
+fn bytes_match(found: &[u8], expected: &[Option<u8>]) -> bool {
+    found.len() == expected.len()
+        && found
+            .iter()
+            .zip(expected)
+            .all(|(&x, &y)| y.is_none() || y == Some(x))
+}
+
+/// Byte patches over our own process unless told otherwise, verified before
+/// they're written and reverted when dropped
+///
+/// # Example
+///
+/// ```ignore
+/// let mut patches = PatchManager::new();
+///
+/// // Flip `jz` to `jmp`, fails with `PatchError::Mismatch` if an update changed the code
+/// patches.apply(
+///     "skip_check",
+///     check.exposed_addr(),
+///     &[Some(0x74), None],
+///     &[Some(0xEB), None],
+/// )?;
+/// ```
+#[derive(Debug, Default)]
+pub struct PatchManager<
+    #[cfg(windows)] S: MemorySource = LocalMemory,
+    #[cfg(not(windows))] S: MemorySource,
+> {
+    source: S,
+    /// Applied patches, in order
+    patches: Vec<Patch>,
+}
+
+#[cfg(windows)]
+impl PatchManager {
+    /// Get manager patching our own process, nothing applied yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<S: MemorySource> PatchManager<S> {
+    /// Get manager patching memory of `source`, nothing applied yet
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use shared::memory::{
+    /// #     patch::PatchManager,
+    /// #     source::{BufferMemory, Protection},
+    /// # };
+    /// let memory = BufferMemory::new(0x1000, vec![0x74, 0x05], Protection::READ_WRITE);
+    /// let mut patches = PatchManager::with_source(memory);
+    /// ```
+    pub fn with_source(source: S) -> Self {
+        Self {
+            source,
+            patches: Vec::new(),
+        }
+    }
+
+    /// Get memory source patches are written through
+    pub fn get_source(&self) -> &S {
+        &self.source
+    }
+
+    /// Get applied patches, in order
+    pub fn get_patches(&self) -> &[Patch] {
+        &self.patches
+    }
+
+    /// Get applied patch named `name`
+    pub fn get_patch(&self, name: &str) -> Option<&Patch> {
+        self.patches.iter().find(|patch| patch.name == name)
+    }
+
+    /// Replace `expected` bytes at `address` with `replacement`, if they're there
+    ///
+    /// `None` in `expected` matches anything, and in `replacement` keeps the
+    /// byte that's there, so any byte (`int3` included) can be both. Fails
+    /// without writing if the bytes differ, if the patch is empty or its halves
+    /// differ in length, or if it overlaps or is named like an applied one.
+    pub fn apply(
+        &mut self,
+        name: &str,
+        address: usize,
+        expected: &[Option<u8>],
+        replacement: &[Option<u8>],
+    ) -> PatchErrOr<()> {
+        if expected.is_empty() || expected.len() != replacement.len() {
+            return Err(PatchError::InvalidSize {
+                name: name.to_owned(),
+                expected: expected.len(),
+                replacement: replacement.len(),
+            }
+            .into());
+        }
+
+        let range = AddressRange::with_size(address, expected.len())?;
+
+        if let Some(other) = self
+            .patches
+            .iter()
+            .find(|patch| patch.name == name || patch.range.intersect(&range).is_some())
+        {
+            return Err(PatchError::Conflict {
+                name: name.to_owned(),
+                other: other.name.clone(),
+                range,
+            }
+            .into());
+        }
+
+        let original = self.read_range(range)?;
+        if !bytes_match(&original, expected) {
+            return Err(PatchError::Mismatch {
+                name: name.to_owned(),
+                address,
+                expected: expected.to_vec(),
+                found: original,
+            }
+            .into());
+        }
+
+        let patched = original
+            .iter()
+            .zip(replacement)
+            .map(|(&x, &y)| y.unwrap_or(x))
+            .collect::<Vec<_>>();
+
+        self.source
+            .write_bytes_unprotected(range.get_start(), &patched)?;
+
+        self.patches.push(Patch {
+            name: name.to_owned(),
+            range,
+            original,
+            patched,
+        });
+
+        Ok(())
+    }
+
+    /// Restore bytes under patch named `name`
+    ///
+    /// Fails without writing if something else changed the patched bytes since,
+    /// the patch is forgotten either way.
+    pub fn revert(&mut self, name: &str) -> PatchErrOr<()> {
+        let index = self
+            .patches
+            .iter()
+            .position(|patch| patch.name == name)
+            .ok_or(Error::CantFind)?;
+
+        let patch = self.patches.remove(index);
+        self.restore(&patch)
+    }
+
+    /// Restore bytes under every patch, latest first, reporting the first failure
+    pub fn revert_all(&mut self) -> PatchErrOr<()> {
+        let mut result = Ok(());
+
+        while let Some(patch) = self.patches.pop() {
+            if let Err(error) = self.restore(&patch) {
+                if result.is_ok() {
+                    result = Err(error);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Read bytes `range` holds
+    fn read_range(&self, range: AddressRange) -> PatchErrOr<Vec<u8>> {
+        let mut buffer = vec![0; range.get_size()];
+        self.source
+            .read_bytes_checked(range.get_start(), &mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Write back `patch`'s original bytes, if its own are still there
+    fn restore(&self, patch: &Patch) -> PatchErrOr<()> {
+        let found = self.read_range(patch.range)?;
+        if found != patch.patched {
+            return Err(PatchError::Mismatch {
+                name: patch.name.clone(),
+                address: patch.range.get_start(),
+                expected: patch.patched.iter().copied().map(Some).collect(),
+                found,
+            }
+            .into());
+        }
+
+        self.source
+            .write_bytes_unprotected(patch.range.get_start(), &patch.original)
+    }
+}
+
+impl<S: MemorySource> Drop for PatchManager<S> {
+    fn drop(&mut self) {
+        if let Err(error) = self.revert_all() {
+            println!("{}", error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::source::{BufferMemory, Protection};
+
+    const BASE: usize = 0x1000;
+
+    /// `jz +5; push ebp; mov ebp, esp` at `BASE`
+    const CODE: [u8; 5] = [0x74, 0x05, 0x55, 0x8B, 0xEC];
+
+    fn manager() -> (BufferMemory, PatchManager<BufferMemory>) {
+        let memory = BufferMemory::new(BASE, CODE.to_vec(), Protection::READ_WRITE);
+        (memory.clone(), PatchManager::with_source(memory))
+    }
+
+    fn error<R: std::fmt::Debug>(result: PatchErrOr<R>) -> PatchError {
+        result
+            .unwrap_err()
+            .downcast_ref::<PatchError>()
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn apply_and_revert() {
+        let (memory, mut patches) = manager();
+
+        // Wildcards match anything, and keep what's there
+        patches
+            .apply("skip", BASE, &[Some(0x74), None], &[Some(0xEB), None])
+            .unwrap();
+        assert_eq!(memory.to_bytes(), [0xEB, 0x05, 0x55, 0x8B, 0xEC]);
+
+        let patch = patches.get_patch("skip").unwrap();
+        assert_eq!(patch.get_range(), AddressRange::new(BASE, BASE + 2));
+        assert_eq!(patch.get_original(), [0x74, 0x05]);
+        assert_eq!(patch.get_patched(), [0xEB, 0x05]);
+
+        patches.revert("skip").unwrap();
+        assert_eq!(memory.to_bytes(), CODE);
+        assert!(patches.get_patches().is_empty());
+        assert!(patches.revert("skip").is_err());
+    }
+
+    #[test]
+    fn conflict() {
+        let (memory, mut patches) = manager();
+        patches
+            .apply("skip", BASE, &[Some(0x74), Some(0x05)], &[Some(0xEB), None])
+            .unwrap();
+
+        // Overlapping, and reusing a name elsewhere
+        assert_eq!(
+            error(patches.apply("nop", BASE + 1, &[None, None], &[Some(0x90), Some(0x90)])),
+            PatchError::Conflict {
+                name: "nop".to_owned(),
+                other: "skip".to_owned(),
+                range: AddressRange::new(BASE + 1, BASE + 3),
+            }
+        );
+        assert!(matches!(
+            error(patches.apply("skip", BASE + 3, &[None], &[Some(0x90)])),
+            PatchError::Conflict { .. }
+        ));
+
+        // Adjacent is fine
+        patches
+            .apply("int3", BASE + 2, &[Some(0x55)], &[Some(0xCC)])
+            .unwrap();
+        assert_eq!(memory.to_bytes(), [0xEB, 0x05, 0xCC, 0x8B, 0xEC]);
+    }
+
+    #[test]
+    fn mismatch() {
+        let (memory, mut patches) = manager();
+
+        assert_eq!(
+            error(patches.apply("skip", BASE, &[Some(0x75), None], &[Some(0xEB), None])),
+            PatchError::Mismatch {
+                name: "skip".to_owned(),
+                address: BASE,
+                expected: vec![Some(0x75), None],
+                found: vec![0x74, 0x05],
+            }
+        );
+
+        // Nothing was written, nor recorded
+        assert_eq!(memory.to_bytes(), CODE);
+        assert!(patches.get_patches().is_empty());
+    }
+
+    #[test]
+    fn invalid_size() {
+        let (memory, mut patches) = manager();
+
+        assert_eq!(
+            error(patches.apply("skip", BASE, &[Some(0x74)], &[Some(0xEB), None])),
+            PatchError::InvalidSize {
+                name: "skip".to_owned(),
+                expected: 1,
+                replacement: 2,
+            }
+        );
+        assert_eq!(
+            error(patches.apply("empty", BASE, &[], &[])),
+            PatchError::InvalidSize {
+                name: "empty".to_owned(),
+                expected: 0,
+                replacement: 0,
+            }
+        );
+
+        assert_eq!(memory.to_bytes(), CODE);
+    }
+
+    #[test]
+    fn revert_on_drop() {
+        let (memory, mut patches) = manager();
+        patches
+            .apply("skip", BASE, &[Some(0x74)], &[Some(0xEB)])
+            .unwrap();
+        patches
+            .apply("int3", BASE + 2, &[Some(0x55)], &[Some(0xCC)])
+            .unwrap();
+        assert_eq!(memory.to_bytes(), [0xEB, 0x05, 0xCC, 0x8B, 0xEC]);
+
+        drop(patches);
+        assert_eq!(memory.to_bytes(), CODE);
+    }
+
+    #[test]
+    fn revert_changed() {
+        let (memory, mut patches) = manager();
+        patches
+            .apply("skip", BASE, &[Some(0x74)], &[Some(0xEB)])
+            .unwrap();
+
+        // Someone else wrote over our patch, leave their bytes be
+        memory.write(BASE, &0x90u8).unwrap();
+        assert!(matches!(
+            error(patches.revert_all()),
+            PatchError::Mismatch { .. }
+        ));
+        assert_eq!(memory.read::<u8>(BASE).unwrap(), 0x90);
+        assert!(patches.get_patches().is_empty());
+    }
+
+    #[test]
+    fn read_only() {
+        // Buffers can't lift protection, so patching read-only ones fails
+        let memory = BufferMemory::new(BASE, CODE.to_vec(), Protection::READ_EXECUTE);
+        let mut patches = PatchManager::with_source(memory.clone());
+
+        assert!(patches
+            .apply("skip", BASE, &[Some(0x74)], &[Some(0xEB)])
+            .is_err());
+        assert!(patches.get_patches().is_empty());
+        assert_eq!(memory.to_bytes(), CODE);
+    }
+}
@@ -0,0 +1,161 @@
+//! Pattern module
+//!
+//! Byte pattern matching, kept apart from `Module` so it's usable (and
+//! testable) without Windows.
+
+/// Byte standing for any byte in patterns
+pub const PATTERN_WILDCARD: u8 = 0xCC;
+
+/// Find offset of `goal_match`-th instance of `pattern` in `haystack`, where
+/// `PATTERN_WILDCARD` bytes match anything
+///
+/// Considers every offset `pattern` fits at, up to and including the one
+/// ending on the last byte. Empty patterns and patterns longer than
+/// `haystack` never match.
+///
+/// # Example
+///
+/// ```rust
+/// # use shared::memory::pattern::find_nth_pattern_in_slice;
+/// assert_eq!(find_nth_pattern_in_slice(&[0x55, 0x8b, 0xec, 0x55], &[0x55], 1), Some(3));
+/// ```
+pub fn find_nth_pattern_in_slice(
+    haystack: &[u8],
+    pattern: &[u8],
+    goal_match: usize,
+) -> Option<usize> {
+    // `windows(0)` panics, and nothing is made of nothing anyway
+    if pattern.is_empty() {
+        return None;
+    }
+
+    // Yields nothing when `pattern` is longer than `haystack`
+    haystack
+        .windows(pattern.len())
+        .enumerate()
+        .filter(|(_, window)| {
+            window
+                .iter()
+                .zip(pattern)
+                .all(|(&x, &y)| x == y || y == PATTERN_WILDCARD)
+        })
+        .nth(goal_match)
+        .map(|(x, _)| x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Xorshift, deterministic so failures reproduce
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, bound: usize) -> usize {
+            (self.next() % bound as u64) as usize
+        }
+
+        /// Bytes from a small alphabet, wildcard included, so matches are common
+        fn bytes(&mut self, length: usize) -> Vec<u8> {
+            const ALPHABET: [u8; 4] = [0x00, 0x55, 0x8B, PATTERN_WILDCARD];
+            (0..length)
+                .map(|_| ALPHABET[self.below(ALPHABET.len())])
+                .collect()
+        }
+    }
+
+    /// Every offset `pattern` matches at, checking each one on its own
+    fn naive(haystack: &[u8], pattern: &[u8]) -> Vec<usize> {
+        if pattern.is_empty() || pattern.len() > haystack.len() {
+            return Vec::new();
+        }
+
+        (0..=haystack.len() - pattern.len())
+            .filter(|&offset| {
+                (0..pattern.len())
+                    .all(|i| pattern[i] == PATTERN_WILDCARD || haystack[offset + i] == pattern[i])
+            })
+            .collect()
+    }
+
+    /// Check every `goal_match` against the naive scan, and one past the last
+    fn check(haystack: &[u8], pattern: &[u8]) {
+        let expected = naive(haystack, pattern);
+
+        for goal_match in 0..=expected.len() {
+            assert_eq!(
+                find_nth_pattern_in_slice(haystack, pattern, goal_match),
+                expected.get(goal_match).copied(),
+                "haystack {:02x?}, pattern {:02x?}, goal {}",
+                haystack,
+                pattern,
+                goal_match
+            );
+        }
+    }
+
+    #[test]
+    fn matches_naive_scan() {
+        let mut rng = Rng(0x9E3779B97F4A7C15);
+
+        for _ in 0..5000 {
+            let (haystack_length, pattern_length) = (rng.below(48), rng.below(8));
+            let haystack = rng.bytes(haystack_length);
+            let pattern = rng.bytes(pattern_length);
+            check(&haystack, &pattern);
+        }
+    }
+
+    #[test]
+    fn matches_naive_scan_on_slices() {
+        let mut rng = Rng(0xD1B54A32D192ED03);
+
+        // Patterns cut from the haystack, so there's at least one match
+        for _ in 0..5000 {
+            let length = 1 + rng.below(48);
+            let haystack = rng.bytes(length);
+            let start = rng.below(haystack.len());
+            let end = start + 1 + rng.below(haystack.len() - start);
+
+            let mut pattern = haystack[start..end].to_vec();
+            let wildcard = rng.below(pattern.len());
+            pattern[wildcard] = PATTERN_WILDCARD;
+
+            assert!(find_nth_pattern_in_slice(&haystack, &pattern, 0).is_some_and(|x| x <= start));
+            check(&haystack, &pattern);
+        }
+    }
+
+    #[test]
+    fn edges() {
+        let haystack = [0x55, 0x8B, 0xEC, 0x55];
+
+        // Ending on the last byte, and being the whole haystack
+        assert_eq!(
+            find_nth_pattern_in_slice(&haystack, &[0xEC, 0x55], 0),
+            Some(2)
+        );
+        assert_eq!(find_nth_pattern_in_slice(&haystack, &[0x55], 1), Some(3));
+        assert_eq!(find_nth_pattern_in_slice(&haystack, &haystack, 0), Some(0));
+        assert_eq!(
+            find_nth_pattern_in_slice(&haystack, &[PATTERN_WILDCARD; 4], 0),
+            Some(0)
+        );
+
+        // Empty either way, and too long
+        assert_eq!(find_nth_pattern_in_slice(&[], &[0x55], 0), None);
+        assert_eq!(find_nth_pattern_in_slice(&haystack, &[], 0), None);
+        assert_eq!(find_nth_pattern_in_slice(&[], &[], 0), None);
+        assert_eq!(
+            find_nth_pattern_in_slice(&haystack, &[PATTERN_WILDCARD; 5], 0),
+            None
+        );
+    }
+}
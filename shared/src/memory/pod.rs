@@ -0,0 +1,46 @@
+//! Plain old data module
+//!
+//! Marks types that can be copied to and from raw bytes, so memory sources
+//! can read them without callers vouching for every bit pattern.
+
+/// Type valid for any bit pattern of its size, with no padding
+///
+/// # Safety
+///
+/// Implementors have to be `#[repr(C)]` (or primitive) and made only of `Pod`
+/// fields, with no padding between them. No references, `bool`, `char`,
+/// enums or niches.
+///
+/// # Example
+///
+/// ```rust
+/// # use shared::memory::pod::Pod;
+/// #[repr(C)]
+/// #[derive(Clone, Copy)]
+/// struct Vector {
+///     x: f32,
+///     y: f32,
+///     z: f32,
+/// }
+///
+/// unsafe impl Pod for Vector {}
+/// ```
+pub unsafe trait Pod: Copy {}
+
+unsafe impl Pod for u8 {}
+unsafe impl Pod for u16 {}
+unsafe impl Pod for u32 {}
+unsafe impl Pod for u64 {}
+unsafe impl Pod for u128 {}
+unsafe impl Pod for usize {}
+unsafe impl Pod for i8 {}
+unsafe impl Pod for i16 {}
+unsafe impl Pod for i32 {}
+unsafe impl Pod for i64 {}
+unsafe impl Pod for i128 {}
+unsafe impl Pod for isize {}
+unsafe impl Pod for f32 {}
+unsafe impl Pod for f64 {}
+unsafe impl<T> Pod for *const T {}
+unsafe impl<T> Pod for *mut T {}
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
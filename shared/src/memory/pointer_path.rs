@@ -0,0 +1,336 @@
+//! Pointer path module
+
+#[cfg(windows)]
+use crate::memory::source::LocalMemory;
+use crate::{
+    error::{Error, PointerPathError},
+    memory::{generic_address::GenericAddress, pod::Pod, source::MemorySource},
+    GenericErrOr,
+};
+use std::{
+    fmt::{self, Display, Formatter},
+    marker::PhantomData,
+};
+
+/// Type for pointer path errors
+pub type PointerPathErrOr<T> = GenericErrOr<T>;
+
+/// Single followed hop of a pointer path
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct PointerHop {
+    address: usize,
+    value: usize,
+    offset: isize,
+}
+
+impl PointerHop {
+    /// Get address pointer was read from
+    pub fn get_address(&self) -> usize {
+        self.address
+    }
+
+    /// Get pointer read
+    pub fn get_value(&self) -> usize {
+        self.value
+    }
+
+    /// Get offset added to pointer
+    pub fn get_offset(&self) -> isize {
+        self.offset
+    }
+
+    /// Get address hop lands on, `value + offset`
+    pub fn get_target(&self) -> usize {
+        self.value.wrapping_add_signed(self.offset)
+    }
+}
+
+/// Typed pointer path, `base -> [offsets...] -> T`
+///
+/// Every hop reads a pointer at the current address and adds its offset, so
+/// `base -> [0x10, 0x4] -> T` has `T` at `*(*base + 0x10) + 0x4`. Every read
+/// is checked, failures say which hop broke.
+pub struct PointerPath<T = ()> {
+    base: usize,
+    offsets: Vec<isize>,
+    /// Exists so we can hold T
+    phantom_data: PhantomData<T>,
+}
+
+impl<T> Clone for PointerPath<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.base, &self.offsets)
+    }
+}
+
+impl<T> Display for PointerPath<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x} -> [", self.base)?;
+
+        for (i, offset) in self.offsets.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+
+            if *offset < 0 {
+                write!(f, "-{:#x}", offset.unsigned_abs())?;
+            } else {
+                write!(f, "{:#x}", offset)?;
+            }
+        }
+
+        write!(f, "] -> {}", std::any::type_name::<T>())
+    }
+}
+
+impl<T> fmt::Debug for PointerPath<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "PointerPath({})", self)
+    }
+}
+
+impl<T> PointerPath<T> {
+    pub fn new(base: usize, offsets: &[isize]) -> Self {
+        Self {
+            base,
+            offsets: offsets.to_vec(),
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Get address path starts at
+    pub fn get_base(&self) -> usize {
+        self.base
+    }
+
+    /// Get offset of every hop
+    pub fn get_offsets(&self) -> &[isize] {
+        &self.offsets
+    }
+
+    /// Get path with another hop, `offset` from the pointer at the current end
+    pub fn then(mut self, offset: isize) -> Self {
+        self.offsets.push(offset);
+        self
+    }
+
+    /// Get same path pointing to `R`
+    pub fn cast<R>(&self) -> PointerPath<R> {
+        PointerPath::new(self.base, &self.offsets)
+    }
+
+    /// Follow path, recording every hop
+    #[cfg(windows)]
+    pub fn trace(&self) -> PointerPathErrOr<Vec<PointerHop>> {
+        self.trace_from(&LocalMemory)
+    }
+
+    /// Follow path through `source`, recording every hop
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// for hop in PointerPath::<i32>::new(base, &[0x10, 0x4]).trace_from(&memory)? {
+    ///     println!("[{:#x}] = {:#x}", hop.get_address(), hop.get_value());
+    /// }
+    /// ```
+    pub fn trace_from<S: MemorySource>(&self, source: &S) -> PointerPathErrOr<Vec<PointerHop>> {
+        let mut result = Vec::with_capacity(self.offsets.len());
+        let mut address = self.base;
+
+        for (hop, &offset) in self.offsets.iter().enumerate() {
+            let fail = |cause: Box<dyn std::error::Error>| -> Box<dyn std::error::Error> {
+                PointerPathError::new(self.to_string(), hop, address, cause).into()
+            };
+
+            let value = source.read::<usize>(address).map_err(fail)?;
+            if value == 0 {
+                return Err(fail(Error::NullPointer.into()));
+            }
+
+            let step = PointerHop {
+                address,
+                value,
+                offset,
+            };
+
+            address = value
+                .checked_add_signed(offset)
+                .ok_or_else(|| fail(Error::Overflow.into()))?;
+
+            result.push(step);
+        }
+
+        Ok(result)
+    }
+
+    /// Follow path, getting address `T` lies at
+    #[cfg(windows)]
+    pub fn resolve(&self) -> PointerPathErrOr<GenericAddress<T>> {
+        self.resolve_from(&LocalMemory)
+    }
+
+    /// Follow path through `source`, getting address `T` lies at
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let health = PointerPath::<i32>::new(local_player, &[0xA8]).resolve()?;
+    /// ```
+    pub fn resolve_from<S: MemorySource>(&self, source: &S) -> PointerPathErrOr<GenericAddress<T>> {
+        Ok(GenericAddress::from(
+            self.trace_from(source)?
+                .last()
+                .map_or(self.base, PointerHop::get_target),
+        ))
+    }
+}
+
+impl<T: Pod> PointerPath<T> {
+    /// Follow path and read `T` at its end
+    #[cfg(windows)]
+    pub fn read(&self) -> PointerPathErrOr<T> {
+        self.read_from(&LocalMemory)
+    }
+
+    /// Follow path through `source` and read `T` at its end
+    pub fn read_from<S: MemorySource>(&self, source: &S) -> PointerPathErrOr<T> {
+        let address = self.resolve_from(source)?.exposed_addr();
+
+        source.read::<T>(address).map_err(|cause| {
+            PointerPathError::new(self.to_string(), self.offsets.len(), address, cause).into()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::source::{BufferMemory, Protection};
+
+    const BASE: usize = 0x1000;
+
+    /// `BASE -> +0x40`, `BASE + 0x50 -> +0x80`, 42 at `BASE + 0x88`, zeroes elsewhere
+    fn memory() -> BufferMemory {
+        let memory = BufferMemory::new(BASE, vec![0; 0x100], Protection::READ_WRITE);
+        memory.write(BASE, &(BASE + 0x40)).unwrap();
+        memory.write(BASE + 0x50, &(BASE + 0x80)).unwrap();
+        memory.write(BASE + 0x88, &42i32).unwrap();
+        memory.write(BASE + 0xF0, &(usize::MAX - 1)).unwrap();
+
+        memory
+    }
+
+    /// Get failing hop, the address it read, and why
+    fn failure<R: std::fmt::Debug>(result: PointerPathErrOr<R>) -> (usize, usize, Error) {
+        let error = result.unwrap_err();
+        let error = error.downcast_ref::<PointerPathError>().unwrap();
+        let cause = *std::error::Error::source(error)
+            .and_then(|cause| cause.downcast_ref::<Error>())
+            .unwrap();
+
+        (error.get_hop(), error.get_address(), cause)
+    }
+
+    #[test]
+    fn multiple_hops() {
+        let memory = memory();
+        let path = PointerPath::<i32>::new(BASE, &[0x10, 0x8]);
+
+        let hops = path.trace_from(&memory).unwrap();
+        assert_eq!(
+            hops,
+            [
+                PointerHop {
+                    address: BASE,
+                    value: BASE + 0x40,
+                    offset: 0x10,
+                },
+                PointerHop {
+                    address: BASE + 0x50,
+                    value: BASE + 0x80,
+                    offset: 0x8,
+                },
+            ]
+        );
+        assert_eq!(hops[1].get_target(), BASE + 0x88);
+
+        assert_eq!(
+            path.resolve_from(&memory).unwrap().exposed_addr(),
+            BASE + 0x88
+        );
+        assert_eq!(path.read_from(&memory).unwrap(), 42);
+
+        // Same path, built a hop at a time
+        let built = PointerPath::<()>::new(BASE, &[]).then(0x10).then(0x8);
+        assert_eq!(built.cast::<i32>().read_from(&memory).unwrap(), 42);
+    }
+
+    #[test]
+    fn no_hops() {
+        let path = PointerPath::<usize>::new(BASE, &[]);
+
+        assert!(path.trace_from(&memory()).unwrap().is_empty());
+        assert_eq!(path.read_from(&memory()).unwrap(), BASE + 0x40);
+    }
+
+    #[test]
+    fn null_hop() {
+        let path = PointerPath::<i32>::new(BASE, &[0x18, 0x0]);
+
+        assert_eq!(
+            failure(path.trace_from(&memory())),
+            (1, BASE + 0x58, Error::NullPointer)
+        );
+        assert_eq!(
+            failure(path.resolve_from(&memory())),
+            (1, BASE + 0x58, Error::NullPointer)
+        );
+    }
+
+    #[test]
+    fn unreadable_hop() {
+        let path = PointerPath::<i32>::new(BASE, &[0x10, 0x1000, 0x0]);
+
+        assert_eq!(
+            failure(path.trace_from(&memory())),
+            (2, BASE + 0x1080, Error::AccessViolation)
+        );
+    }
+
+    #[test]
+    fn unreadable_end() {
+        // Every hop is fine, `T` isn't readable, it's past the last one
+        let path = PointerPath::<i32>::new(BASE, &[0x10, 0x1000]);
+
+        assert_eq!(
+            path.resolve_from(&memory()).unwrap().exposed_addr(),
+            BASE + 0x1080
+        );
+        assert_eq!(
+            failure(path.read_from(&memory())),
+            (2, BASE + 0x1080, Error::AccessViolation)
+        );
+    }
+
+    #[test]
+    fn overflowing_hop() {
+        let path = PointerPath::<i32>::new(BASE + 0xF0, &[0x10]);
+
+        assert_eq!(
+            failure(path.trace_from(&memory())),
+            (0, BASE + 0xF0, Error::Overflow)
+        );
+    }
+
+    #[test]
+    fn display() {
+        let path = PointerPath::<i32>::new(BASE, &[0x10, -0x8]);
+
+        assert_eq!(path.to_string(), "0x1000 -> [0x10, -0x8] -> i32");
+        assert_eq!(
+            format!("{:?}", path.cast::<()>()),
+            "PointerPath(0x1000 -> [0x10, -0x8] -> ())"
+        );
+    }
+}
@@ -0,0 +1,148 @@
+//! Protection guard module
+
+use crate::{
+    error::Error,
+    memory::{
+        range::{AddressRange, PAGE_SIZE},
+        regions::Regions,
+        source::{LocalMemory, Protection},
+    },
+    GenericErrOr,
+};
+use std::io;
+use winapi::{
+    shared::minwindef::DWORD,
+    um::{
+        memoryapi::VirtualProtect,
+        processthreadsapi::{FlushInstructionCache, GetCurrentProcess},
+    },
+};
+
+/// Type for protection guard errors
+pub type ProtectionErrOr<T> = GenericErrOr<T>;
+
+/// Changes protection of a range of our own memory, restoring what every page
+/// had on drop, early returns and panics included
+///
+/// Instruction cache is flushed on drop if any of the range was, or was made,
+/// executable, so code writes are seen by every core.
+///
+/// # Example
+///
+/// ```ignore
+/// // NOP out a check in game code
+/// let range = AddressRange::with_size(check, 2)?;
+/// let _guard = ProtectionGuard::writable(range)?;
+/// GenericAddress::<()>::from(check).write([0x90u8; 2])?;
+/// ```
+#[derive(Debug)]
+pub struct ProtectionGuard {
+    range: AddressRange,
+    /// Every region we changed, with the raw `PAGE_*` protection it had
+    original: Vec<(AddressRange, DWORD)>,
+    flush: bool,
+}
+
+impl ProtectionGuard {
+    /// Set protection of pages `range` touches to `protection`
+    pub fn new(range: AddressRange, protection: Protection) -> ProtectionErrOr<Self> {
+        Self::protect(range, |_| protection)
+    }
+
+    /// Make pages `range` touches writable, keeping executable pages executable
+    /// so code running from them doesn't fault while we hold the guard
+    pub fn writable(range: AddressRange) -> ProtectionErrOr<Self> {
+        Self::protect(range, |current| {
+            if current.is_executable() {
+                Protection::READ_WRITE_EXECUTE
+            } else {
+                Protection::READ_WRITE
+            }
+        })
+    }
+
+    /// Get range guard was asked to cover
+    pub fn get_range(&self) -> AddressRange {
+        self.range
+    }
+
+    /// Protect every region `range` touches with what `f` maps its current protection to
+    fn protect(range: AddressRange, f: impl Fn(Protection) -> Protection) -> ProtectionErrOr<Self> {
+        if range.is_empty() {
+            return Err(Error::AccessViolation.into());
+        }
+
+        // Protection is per page, so cover whole pages
+        let start = range.get_start() & !(PAGE_SIZE - 1);
+        let end = range
+            .get_end()
+            .checked_add(PAGE_SIZE - 1)
+            .ok_or(Error::Overflow)?
+            & !(PAGE_SIZE - 1);
+
+        // Built as we go, so anything already changed is restored if we fail midway
+        let mut result = Self {
+            range,
+            original: Vec::new(),
+            flush: false,
+        };
+
+        for region in Regions::new(&LocalMemory, AddressRange::new(start, end)) {
+            if !region.is_committed() {
+                return Err(Error::AccessViolation.into());
+            }
+
+            let current = region.get_protection();
+            let protection = f(current);
+            let mut old = 0;
+
+            if unsafe {
+                VirtualProtect(
+                    region.get_start() as _,
+                    region.get_size(),
+                    protection.to_page_protect(),
+                    &mut old,
+                )
+            } == 0
+            {
+                return Err(io::Error::last_os_error().into());
+            }
+
+            result.flush |= current.is_executable() || protection.is_executable();
+            result.original.push((region.get_range(), old));
+        }
+
+        // Region walk stopped short of the range, part of it isn't mapped
+        if result.original.last().map(|(last, _)| last.get_end()) != Some(end) {
+            return Err(Error::AccessViolation.into());
+        }
+
+        Ok(result)
+    }
+}
+
+impl Drop for ProtectionGuard {
+    fn drop(&mut self) {
+        for (range, protection) in self.original.drain(..).rev() {
+            let mut old = 0;
+            unsafe {
+                VirtualProtect(
+                    range.get_start() as _,
+                    range.get_size(),
+                    protection,
+                    &mut old,
+                )
+            };
+        }
+
+        if self.flush {
+            unsafe {
+                FlushInstructionCache(
+                    GetCurrentProcess(),
+                    self.range.get_start() as _,
+                    self.range.get_size(),
+                )
+            };
+        }
+    }
+}
@@ -0,0 +1,284 @@
+//! Address range module
+
+use crate::{error::Error, GenericErrOr};
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::Range,
+};
+
+/// Type for address range errors
+pub type RangeErrOr<T> = GenericErrOr<T>;
+
+/// Page size of every target we support
+pub const PAGE_SIZE: usize = 0x1000;
+
+/// Half-open range of addresses, `start..end`
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct AddressRange {
+    start: usize,
+    end: usize,
+}
+
+impl From<Range<usize>> for AddressRange {
+    fn from(range: Range<usize>) -> Self {
+        Self::new(range.start, range.end)
+    }
+}
+
+impl From<AddressRange> for Range<usize> {
+    fn from(range: AddressRange) -> Self {
+        range.start..range.end
+    }
+}
+
+impl Display for AddressRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}..{:#x}", self.start, self.end)
+    }
+}
+
+impl AddressRange {
+    /// Get range from `start` up to `end`, empty if `end` comes before `start`
+    pub fn new(start: usize, end: usize) -> Self {
+        Self {
+            start,
+            end: end.max(start),
+        }
+    }
+
+    /// Get range of `size` bytes from `start`, if it doesn't overflow
+    pub fn with_size(start: usize, size: usize) -> RangeErrOr<Self> {
+        start
+            .checked_add(size)
+            .map(|end| Self::new(start, end))
+            .ok_or_else(|| Error::Overflow.into())
+    }
+
+    /// Get range start address
+    pub fn get_start(&self) -> usize {
+        self.start
+    }
+
+    /// Get address right past the range
+    pub fn get_end(&self) -> usize {
+        self.end
+    }
+
+    /// Get range size
+    pub fn get_size(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Whether range has no addresses
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Whether `address` lies in range
+    pub fn contains(&self, address: usize) -> bool {
+        (self.start..self.end).contains(&address)
+    }
+
+    /// Whether all of `other` lies in range, empty ranges lie anywhere
+    pub fn contains_range(&self, other: &Self) -> bool {
+        other.is_empty() || (other.start >= self.start && other.end <= self.end)
+    }
+
+    /// Get addresses both ranges have, if any
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use shared::{error::Error, memory::range::AddressRange};
+    /// # let text = AddressRange::new(0x1000, 0x2000);
+    /// # let function = AddressRange::with_size(0x1F80, 0x100)?;
+    /// // Scan only the part of a function that lies in .text
+    /// let range = function.intersect(&text).ok_or(Error::CantFind)?;
+    /// assert_eq!(range, AddressRange::new(0x1F80, 0x2000));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn intersect(&self, other: &Self) -> Option<Self> {
+        let result = Self::new(self.start.max(other.start), self.end.min(other.end));
+        (!result.is_empty()).then_some(result)
+    }
+
+    /// Split range in two at `address`, clamped to the range
+    pub fn split_at(&self, address: usize) -> (Self, Self) {
+        let address = address.clamp(self.start, self.end);
+        (Self::new(self.start, address), Self::new(address, self.end))
+    }
+
+    /// Iterate over the pages range touches, each clamped to the range
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use shared::memory::range::AddressRange;
+    /// # let range = AddressRange::new(0x1800, 0x3000);
+    /// for page in range.pages() {
+    ///     println!("{}", page);
+    /// }
+    /// ```
+    pub fn pages(&self) -> Pages {
+        Pages {
+            current: self.start,
+            end: self.end,
+        }
+    }
+}
+
+/// Iterator over the pages of an address range, see `AddressRange::pages`
+#[derive(Debug, Clone)]
+pub struct Pages {
+    current: usize,
+    end: usize,
+}
+
+impl Iterator for Pages {
+    type Item = AddressRange;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current >= self.end {
+            return None;
+        }
+
+        // Next page boundary, or the end if it's closer (or past the address space)
+        let boundary = (self.current & !(PAGE_SIZE - 1))
+            .checked_add(PAGE_SIZE)
+            .map_or(self.end, |boundary| boundary.min(self.end));
+
+        let result = AddressRange::new(self.current, boundary);
+        self.current = boundary;
+
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: usize, end: usize) -> AddressRange {
+        AddressRange::new(start, end)
+    }
+
+    #[test]
+    fn new() {
+        assert_eq!(range(0x2000, 0x3000).get_size(), 0x1000);
+        assert!(!range(0x2000, 0x3000).is_empty());
+
+        // Backwards ranges are empty, at their start
+        let backwards = range(0x3000, 0x2000);
+        assert!(backwards.is_empty());
+        assert_eq!(backwards.get_start(), 0x3000);
+        assert_eq!(backwards.get_end(), 0x3000);
+    }
+
+    #[test]
+    fn with_size() {
+        assert_eq!(
+            AddressRange::with_size(0x2000, 0x1000).unwrap(),
+            range(0x2000, 0x3000)
+        );
+        assert_eq!(
+            AddressRange::with_size(usize::MAX - 1, 1).unwrap(),
+            range(usize::MAX - 1, usize::MAX)
+        );
+
+        let error = AddressRange::with_size(usize::MAX, 1).unwrap_err();
+        assert_eq!(error.downcast_ref::<Error>(), Some(&Error::Overflow));
+    }
+
+    #[test]
+    fn contains() {
+        let outer = range(0x2000, 0x3000);
+
+        assert!(outer.contains(0x2000));
+        assert!(outer.contains(0x2FFF));
+        assert!(!outer.contains(0x3000));
+
+        assert!(outer.contains_range(&outer));
+        assert!(outer.contains_range(&range(0x2800, 0x3000)));
+        assert!(!outer.contains_range(&range(0x2800, 0x3001)));
+        assert!(!outer.contains_range(&range(0x1000, 0x2800)));
+
+        // Empty ranges lie anywhere, even out of the range, and in empty ranges
+        assert!(outer.contains_range(&range(0x9000, 0x9000)));
+        assert!(range(0x5000, 0x5000).contains_range(&range(0x9000, 0x9000)));
+        assert!(!range(0x5000, 0x5000).contains_range(&outer));
+    }
+
+    #[test]
+    fn intersect() {
+        let a = range(0x2000, 0x3000);
+
+        assert_eq!(
+            a.intersect(&range(0x2800, 0x4000)),
+            Some(range(0x2800, 0x3000))
+        );
+        assert_eq!(a.intersect(&range(0x1000, 0x5000)), Some(a));
+        assert_eq!(a.intersect(&a), Some(a));
+
+        // Touching ranges share no address
+        assert_eq!(a.intersect(&range(0x3000, 0x4000)), None);
+        assert_eq!(a.intersect(&range(0x1000, 0x2000)), None);
+        assert_eq!(a.intersect(&range(0x8000, 0x9000)), None);
+        assert_eq!(a.intersect(&range(0x2800, 0x2800)), None);
+    }
+
+    #[test]
+    fn split_at() {
+        let a = range(0x2000, 0x3000);
+
+        assert_eq!(
+            a.split_at(0x2800),
+            (range(0x2000, 0x2800), range(0x2800, 0x3000))
+        );
+
+        // Clamped to the range, one half is empty
+        assert_eq!(a.split_at(0x1000), (range(0x2000, 0x2000), a));
+        assert_eq!(a.split_at(0x9000), (a, range(0x3000, 0x3000)));
+        assert_eq!(a.split_at(0x3000), (a, range(0x3000, 0x3000)));
+    }
+
+    #[test]
+    fn pages() {
+        let pages = |start, end| range(start, end).pages().collect::<Vec<_>>();
+
+        assert_eq!(
+            pages(0x2800, 0x4800),
+            [
+                range(0x2800, 0x3000),
+                range(0x3000, 0x4000),
+                range(0x4000, 0x4800)
+            ]
+        );
+
+        // Ending right on a boundary doesn't add an empty page
+        assert_eq!(
+            pages(0x2000, 0x4000),
+            [range(0x2000, 0x3000), range(0x3000, 0x4000)]
+        );
+        assert_eq!(pages(0x2FFF, 0x3000), [range(0x2FFF, 0x3000)]);
+        assert!(pages(0x2000, 0x2000).is_empty());
+
+        // Last page's boundary is past the address space
+        let top = usize::MAX - 0x17FF;
+        assert_eq!(
+            pages(top, usize::MAX),
+            [
+                range(top, usize::MAX - 0xFFF),
+                range(usize::MAX - 0xFFF, usize::MAX)
+            ]
+        );
+    }
+
+    #[test]
+    fn conversions() {
+        let a = range(0x2000, 0x3000);
+
+        assert_eq!(AddressRange::from(0x2000..0x3000), a);
+        assert_eq!(Range::from(a), 0x2000..0x3000);
+        assert_eq!(a.to_string(), "0x2000..0x3000");
+    }
+}
@@ -0,0 +1,222 @@
+//! Memory regions module
+//!
+//! Walks the regions of a memory source, the way `VirtualQuery` sees them for
+//! processes, and as captured for snapshots. Covers what the loader list
+//! doesn't: heap allocations, JIT'd code and manually mapped images.
+
+use crate::memory::{
+    module_info::ModuleInfo,
+    range::AddressRange,
+    source::{MemoryRegion, MemorySource},
+};
+
+/// Iterator over the regions of a memory source within a range, committed or not,
+/// each clamped to the range
+///
+/// # Example
+///
+/// ```ignore
+/// // Executable memory no module claims, a manually mapped image or JIT'd code
+/// let modules = Module::enumerate();
+/// for (region, _) in Regions::all(&LocalMemory)
+///     .with_owners(&modules)
+///     .filter(|(region, owner)| region.is_committed() && owner.is_none())
+///     .filter(|(region, _)| region.get_protection().is_executable())
+/// {
+///     println!("{}", region.get_range());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Regions<'a, S: MemorySource> {
+    source: &'a S,
+    current: usize,
+    end: usize,
+}
+
+impl<'a, S: MemorySource> Regions<'a, S> {
+    /// Walk regions of `source` touching `range`
+    pub fn new(source: &'a S, range: AddressRange) -> Self {
+        Self {
+            source,
+            current: range.get_start(),
+            end: range.get_end(),
+        }
+    }
+
+    /// Walk every region of `source`
+    pub fn all(source: &'a S) -> Self {
+        Self::new(source, AddressRange::new(0, usize::MAX))
+    }
+
+    /// Pair every region with the module in `modules` owning it, if any
+    ///
+    /// A region is owned by the module whose image contains its start, regions
+    /// never straddle images.
+    pub fn with_owners<'m>(
+        self,
+        modules: &'m [ModuleInfo],
+    ) -> impl Iterator<Item = (MemoryRegion, Option<&'m ModuleInfo>)> + 'a
+    where
+        'm: 'a,
+    {
+        self.map(move |region| {
+            let owner = modules
+                .iter()
+                .find(|info| info.contains(region.get_start()));
+
+            (region, owner)
+        })
+    }
+}
+
+impl<'a, S: MemorySource> Iterator for Regions<'a, S> {
+    type Item = MemoryRegion;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current >= self.end {
+            return None;
+        }
+
+        // Querying fails past the highest user address, that's the end for us
+        let region = self.source.query(self.current).ok()?;
+        if region.get_end() <= self.current {
+            self.current = self.end;
+            return None;
+        }
+
+        let result = MemoryRegion::new(
+            self.current.max(region.get_start()),
+            self.end.min(region.get_end()),
+            region.is_committed(),
+            region.get_protection(),
+        );
+
+        self.current = result.get_end();
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::{
+        snapshot::{Snapshot, SnapshotMemory},
+        source::{BufferMemory, Protection},
+    };
+    use std::mem::size_of;
+
+    const BASE: usize = 0x10000;
+    const IMAGE_SIZE: usize = 0x1000;
+    const HEAP: usize = BASE + 0x2000;
+    const HEAP_SIZE: usize = 0x800;
+
+    fn client() -> ModuleInfo {
+        ModuleInfo::new(BASE, IMAGE_SIZE, String::new(), "client.dll".to_owned())
+    }
+
+    /// Snapshot of client.dll and a heap allocation, unmapped between them
+    fn snapshot() -> SnapshotMemory {
+        let machine: u16 = if size_of::<usize>() == 8 {
+            0x8664
+        } else {
+            0x14C
+        };
+        let memory = BufferMemory::new(BASE, vec![0; 0x3000], Protection::READ_WRITE);
+        memory.write(BASE + 0x3C, &0x80u32).unwrap();
+        memory.write(BASE + 0x84, &machine).unwrap();
+
+        Snapshot::capture(&memory, vec![client()], &[(HEAP, HEAP_SIZE)])
+            .unwrap()
+            .try_into()
+            .unwrap()
+    }
+
+    fn ranges<S: MemorySource>(regions: Regions<S>) -> Vec<(AddressRange, bool)> {
+        regions
+            .map(|region| (region.get_range(), region.is_committed()))
+            .collect()
+    }
+
+    #[test]
+    fn all() {
+        let memory = BufferMemory::new(BASE, vec![0; 0x100], Protection::READ);
+
+        assert_eq!(
+            ranges(Regions::all(&memory)),
+            [
+                (AddressRange::new(0, BASE), false),
+                (AddressRange::new(BASE, BASE + 0x100), true),
+                (AddressRange::new(BASE + 0x100, usize::MAX), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn gaps() {
+        assert_eq!(
+            ranges(Regions::new(
+                &snapshot(),
+                AddressRange::new(BASE, HEAP + HEAP_SIZE)
+            )),
+            [
+                (AddressRange::new(BASE, BASE + IMAGE_SIZE), true),
+                (AddressRange::new(BASE + IMAGE_SIZE, HEAP), false),
+                (AddressRange::new(HEAP, HEAP + HEAP_SIZE), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn clamped() {
+        let memory = snapshot();
+
+        // Starts and ends halfway through regions
+        assert_eq!(
+            ranges(Regions::new(
+                &memory,
+                AddressRange::new(BASE + 0x800, HEAP + 0x400)
+            )),
+            [
+                (AddressRange::new(BASE + 0x800, BASE + IMAGE_SIZE), true),
+                (AddressRange::new(BASE + IMAGE_SIZE, HEAP), false),
+                (AddressRange::new(HEAP, HEAP + 0x400), true),
+            ]
+        );
+
+        // Within one region
+        assert_eq!(
+            ranges(Regions::new(
+                &memory,
+                AddressRange::new(HEAP + 0x100, HEAP + 0x200)
+            )),
+            [(AddressRange::new(HEAP + 0x100, HEAP + 0x200), true)]
+        );
+
+        assert!(ranges(Regions::new(&memory, AddressRange::new(HEAP, HEAP))).is_empty());
+    }
+
+    #[test]
+    fn with_owners() {
+        let memory = snapshot();
+        let modules = [
+            client(),
+            ModuleInfo::new(HEAP, HEAP_SIZE, String::new(), "mapped.dll".to_owned()),
+        ];
+
+        let owners = Regions::new(&memory, AddressRange::new(BASE + 0x800, usize::MAX))
+            .with_owners(&modules)
+            .map(|(region, owner)| (region.get_start(), owner.map(ModuleInfo::get_base_name)))
+            .collect::<Vec<_>>();
+
+        // Owned by whoever contains the start, even when clamped
+        assert_eq!(
+            owners,
+            [
+                (BASE + 0x800, Some("client.dll")),
+                (BASE + IMAGE_SIZE, None),
+                (HEAP, Some("mapped.dll")),
+                (HEAP + HEAP_SIZE, None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,667 @@
+//! Snapshot module
+//!
+//! Captures loaded modules and chosen memory ranges of a memory source to a
+//! file, and replays them as a memory source of their own, so scans, pointer
+//! chains and interface walks can be reproduced away from the game.
+//!
+//! # Format
+//!
+//! Everything is little-endian, addresses and sizes are stored as `u64`.
+//!
+//! ```text
+//! magic          [u8; 8]   b"TFSNAP\0\0"
+//! version        u32       SNAPSHOT_VERSION
+//! pointer width  u8        pointer size of the captured process, 4 or 8
+//! module count   u32
+//! modules        base: u64, size: u64, full path: string, base name: string
+//! region count   u32
+//! regions        start: u64, protection: u8, length: u64, bytes: [u8; length]
+//! ```
+//!
+//! Pointer width comes from the machine the captured modules were built for,
+//! snapshots are only loaded by builds of the same width. Strings are a `u32`
+//! length followed by as many UTF-8 bytes. Protection bits
+//! are read (1), write (2) and execute (4). Regions are sorted and disjoint.
+
+use crate::{
+    error::Error,
+    memory::{
+        module_info::ModuleInfo,
+        range::AddressRange,
+        source::{MemoryRegion, MemorySource, Protection},
+    },
+    GenericErrOr,
+};
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    mem::size_of,
+    path::Path,
+    sync::Arc,
+};
+
+/// Type for snapshot errors
+pub type SnapshotErrOr<T> = GenericErrOr<T>;
+
+/// Identifies snapshot files
+pub const SNAPSHOT_MAGIC: [u8; 8] = *b"TFSNAP\0\0";
+
+/// Version of the format we write, bumped on every layout change
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Upper bound on strings in snapshot files, guards against corrupted lengths
+const MAX_STRING_LENGTH: usize = 0x1000;
+
+/// Captured run of readable memory
+#[derive(Debug, Clone)]
+pub struct SnapshotRegion {
+    start: usize,
+    protection: Protection,
+    bytes: Vec<u8>,
+}
+
+impl SnapshotRegion {
+    pub fn new(start: usize, protection: Protection, bytes: Vec<u8>) -> Self {
+        Self {
+            start,
+            protection,
+            bytes,
+        }
+    }
+
+    /// Get region start address
+    pub fn get_start(&self) -> usize {
+        self.start
+    }
+
+    /// Get address right past the region
+    pub fn get_end(&self) -> usize {
+        self.start + self.bytes.len()
+    }
+
+    /// Get addresses region spans
+    pub fn get_range(&self) -> AddressRange {
+        AddressRange::new(self.start, self.get_end())
+    }
+
+    /// Get protection region had when captured
+    pub fn get_protection(&self) -> Protection {
+        self.protection
+    }
+
+    /// Get captured bytes
+    pub fn get_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Whether `address` lies in region
+    pub fn contains(&self, address: usize) -> bool {
+        address >= self.start && address < self.get_end()
+    }
+}
+
+/// Captured modules and memory of a process
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pointer_width: u8,
+    modules: Vec<ModuleInfo>,
+    regions: Vec<SnapshotRegion>,
+}
+
+/// Sort `ranges` and merge the overlapping ones
+fn merge_ranges(mut ranges: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    ranges.retain(|&(start, end)| start < end);
+    ranges.sort_unstable();
+
+    let mut result: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match result.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => result.push((start, end)),
+        }
+    }
+
+    result
+}
+
+/// Encode `protection` to format bits
+fn protection_to_bits(protection: Protection) -> u8 {
+    protection.is_readable() as u8
+        | (protection.is_writable() as u8) << 1
+        | (protection.is_executable() as u8) << 2
+}
+
+/// Decode `protection` from format bits
+fn protection_from_bits(bits: u8) -> Protection {
+    Protection::new(bits & 1 != 0, bits & 2 != 0, bits & 4 != 0)
+}
+
+// Writing helpers
+
+fn write_u8(writer: &mut impl Write, value: u8) -> SnapshotErrOr<()> {
+    Ok(writer.write_all(&[value])?)
+}
+
+fn write_u32(writer: &mut impl Write, value: usize) -> SnapshotErrOr<()> {
+    let value = u32::try_from(value).map_err(|_| Error::InvalidSnapshot)?;
+    Ok(writer.write_all(&value.to_le_bytes())?)
+}
+
+fn write_u64(writer: &mut impl Write, value: usize) -> SnapshotErrOr<()> {
+    Ok(writer.write_all(&(value as u64).to_le_bytes())?)
+}
+
+fn write_string(writer: &mut impl Write, value: &str) -> SnapshotErrOr<()> {
+    write_u32(writer, value.len())?;
+    Ok(writer.write_all(value.as_bytes())?)
+}
+
+// Reading helpers, truncation is reported as an invalid snapshot
+
+fn read_exact(reader: &mut impl Read, buffer: &mut [u8]) -> SnapshotErrOr<()> {
+    reader
+        .read_exact(buffer)
+        .map_err(|_| Error::InvalidSnapshot.into())
+}
+
+fn read_u8(reader: &mut impl Read) -> SnapshotErrOr<u8> {
+    let mut buffer = [0; 1];
+    read_exact(reader, &mut buffer)?;
+    Ok(buffer[0])
+}
+
+fn read_u32(reader: &mut impl Read) -> SnapshotErrOr<usize> {
+    let mut buffer = [0; 4];
+    read_exact(reader, &mut buffer)?;
+    Ok(u32::from_le_bytes(buffer) as usize)
+}
+
+fn read_u64(reader: &mut impl Read) -> SnapshotErrOr<usize> {
+    let mut buffer = [0; 8];
+    read_exact(reader, &mut buffer)?;
+
+    // Addresses of a wider process don't fit ours
+    usize::try_from(u64::from_le_bytes(buffer)).map_err(|_| Error::InvalidSnapshot.into())
+}
+
+fn read_string(reader: &mut impl Read) -> SnapshotErrOr<String> {
+    let length = read_u32(reader)?;
+    if length > MAX_STRING_LENGTH {
+        return Err(Error::InvalidSnapshot.into());
+    }
+
+    let mut buffer = vec![0; length];
+    read_exact(reader, &mut buffer)?;
+    String::from_utf8(buffer).map_err(|_| Error::InvalidSnapshot.into())
+}
+
+impl Snapshot {
+    /// Capture `modules`, and every `(start, size)` in `extra`, from `source`
+    ///
+    /// Only committed and readable memory is captured, the rest of the ranges
+    /// will read as unmapped on replay. Modules have to agree on their pointer
+    /// width, there's at least one for that reason.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let memory = ProcessMemory::open(find_process_id("hl2.exe")?)?;
+    /// let modules = Module::enumerate_from(&memory, memory.get_peb()?)?;
+    ///
+    /// Snapshot::capture(&memory, modules, &[(0x2A000000, 0x1000)])?.save("hl2.snap")?;
+    /// ```
+    pub fn capture<S: MemorySource>(
+        source: &S,
+        modules: Vec<ModuleInfo>,
+        extra: &[(usize, usize)],
+    ) -> SnapshotErrOr<Self> {
+        let mut pointer_width = None;
+        for info in &modules {
+            let width = info.read_pointer_width(source)?;
+            if pointer_width.is_some_and(|pointer_width| pointer_width != width) {
+                return Err(Error::PointerWidthMismatch.into());
+            }
+
+            pointer_width = Some(width);
+        }
+
+        let pointer_width = pointer_width.ok_or(Error::CantFind)? as u8;
+
+        let ranges = merge_ranges(
+            modules
+                .iter()
+                .map(|info| (info.get_base(), info.get_end()))
+                .chain(
+                    extra
+                        .iter()
+                        .map(|&(start, size)| (start, start.saturating_add(size))),
+                )
+                .collect(),
+        );
+
+        let mut regions = Vec::new();
+        for (start, end) in ranges {
+            // Walk every region the range touches, keeping readable ones
+            let mut current = start;
+            while current < end {
+                let region = source.query(current)?;
+                if region.get_end() <= current {
+                    break;
+                }
+
+                let chunk_end = region.get_end().min(end);
+                if region.is_readable() {
+                    let mut bytes = vec![0; chunk_end - current];
+                    source.read_bytes_checked(current, &mut bytes)?;
+                    regions.push(SnapshotRegion::new(current, region.get_protection(), bytes));
+                }
+
+                current = chunk_end;
+            }
+        }
+
+        Ok(Self {
+            pointer_width,
+            modules,
+            regions,
+        })
+    }
+
+    /// Get pointer width (in bytes) of the captured process
+    pub fn get_pointer_width(&self) -> u8 {
+        self.pointer_width
+    }
+
+    /// Get modules loaded when captured
+    pub fn get_modules(&self) -> &[ModuleInfo] {
+        &self.modules
+    }
+
+    /// Get captured regions, sorted by address
+    pub fn get_regions(&self) -> &[SnapshotRegion] {
+        &self.regions
+    }
+
+    /// Get region `address` lies in, if captured
+    pub fn find_region(&self, address: usize) -> Option<&SnapshotRegion> {
+        let index = self
+            .regions
+            .partition_point(|region| region.get_end() <= address);
+
+        self.regions
+            .get(index)
+            .filter(|region| region.contains(address))
+    }
+
+    /// Serialize snapshot to `writer`
+    pub fn write_to(&self, writer: &mut impl Write) -> SnapshotErrOr<()> {
+        writer.write_all(&SNAPSHOT_MAGIC)?;
+        writer.write_all(&SNAPSHOT_VERSION.to_le_bytes())?;
+        write_u8(writer, self.pointer_width)?;
+
+        write_u32(writer, self.modules.len())?;
+        for info in &self.modules {
+            write_u64(writer, info.get_base())?;
+            write_u64(writer, info.get_size())?;
+            write_string(writer, info.get_full_path())?;
+            write_string(writer, info.get_base_name())?;
+        }
+
+        write_u32(writer, self.regions.len())?;
+        for region in &self.regions {
+            write_u64(writer, region.start)?;
+            write_u8(writer, protection_to_bits(region.protection))?;
+            write_u64(writer, region.bytes.len())?;
+            writer.write_all(&region.bytes)?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether snapshot was captured from a process as wide as we are
+    pub fn is_native(&self) -> bool {
+        self.pointer_width as usize == size_of::<usize>()
+    }
+
+    /// Deserialize snapshot from `reader`, if captured from a process as wide
+    /// as we are
+    pub fn read_from(reader: &mut impl Read) -> SnapshotErrOr<Self> {
+        let mut magic = [0; SNAPSHOT_MAGIC.len()];
+        read_exact(reader, &mut magic)?;
+
+        let mut version = [0; 4];
+        read_exact(reader, &mut version)?;
+
+        if magic != SNAPSHOT_MAGIC || u32::from_le_bytes(version) != SNAPSHOT_VERSION {
+            return Err(Error::InvalidSnapshot.into());
+        }
+
+        // Addresses and structure layouts of another width don't replay here
+        let pointer_width = read_u8(reader)?;
+        if pointer_width as usize != size_of::<usize>() {
+            return Err(Error::InvalidSnapshot.into());
+        }
+
+        let mut modules = Vec::new();
+        for _ in 0..read_u32(reader)? {
+            let base = read_u64(reader)?;
+            let size = read_u64(reader)?;
+            let full_path = read_string(reader)?;
+            let base_name = read_string(reader)?;
+
+            modules.push(ModuleInfo::new(base, size, full_path, base_name));
+        }
+
+        let mut regions: Vec<SnapshotRegion> = Vec::new();
+        for _ in 0..read_u32(reader)? {
+            let start = read_u64(reader)?;
+            let protection = protection_from_bits(read_u8(reader)?);
+            let length = read_u64(reader)?;
+
+            // Keep lookups sound, regions have to be sorted and disjoint
+            if start.checked_add(length).is_none()
+                || regions.last().is_some_and(|last| start < last.get_end())
+            {
+                return Err(Error::InvalidSnapshot.into());
+            }
+
+            // Read through `take` so a corrupted length can't allocate the world
+            let mut bytes = Vec::new();
+            reader
+                .by_ref()
+                .take(length as u64)
+                .read_to_end(&mut bytes)?;
+            if bytes.len() != length {
+                return Err(Error::InvalidSnapshot.into());
+            }
+
+            regions.push(SnapshotRegion::new(start, protection, bytes));
+        }
+
+        Ok(Self {
+            pointer_width,
+            modules,
+            regions,
+        })
+    }
+
+    /// Save snapshot to file at `path`
+    pub fn save(&self, path: impl AsRef<Path>) -> SnapshotErrOr<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        Ok(writer.flush()?)
+    }
+
+    /// Load snapshot from file at `path`
+    pub fn load(path: impl AsRef<Path>) -> SnapshotErrOr<Self> {
+        Self::read_from(&mut BufReader::new(File::open(path)?))
+    }
+}
+
+/// Replays a snapshot, captured regions read as they were, everything else
+/// is unmapped
+///
+/// Only snapshots of processes as wide as we are replay. Replays are read-only, regions report their captured protection without
+/// write access. Cheap to clone, clones share the same snapshot.
+///
+/// # Example
+///
+/// ```no_run
+/// # use shared::{error::Error, memory::snapshot::SnapshotMemory};
+/// let memory = SnapshotMemory::load("hl2.snap")?;
+/// let engine = memory
+///     .get_snapshot()
+///     .get_modules()
+///     .iter()
+///     .find(|info| info.matches("engine.dll"))
+///     .ok_or(Error::CantFind)?
+///     .to_module_with(memory.clone());
+///
+/// let cvar = engine.get_exports()["cvar"].deref_from(&memory, 1)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct SnapshotMemory {
+    snapshot: Arc<Snapshot>,
+}
+
+impl TryFrom<Snapshot> for SnapshotMemory {
+    type Error = Box<dyn std::error::Error>;
+
+    fn try_from(snapshot: Snapshot) -> SnapshotErrOr<Self> {
+        if !snapshot.is_native() {
+            return Err(Error::InvalidSnapshot.into());
+        }
+
+        Ok(Self {
+            snapshot: Arc::new(snapshot),
+        })
+    }
+}
+
+impl SnapshotMemory {
+    /// Load snapshot from file at `path` to replay it
+    pub fn load(path: impl AsRef<Path>) -> SnapshotErrOr<Self> {
+        Snapshot::load(path)?.try_into()
+    }
+
+    /// Get replayed snapshot
+    pub fn get_snapshot(&self) -> &Snapshot {
+        &self.snapshot
+    }
+
+    /// Get bytes `size` bytes from `address` lie in, if all within one region
+    fn get_bytes(&self, address: usize, size: usize) -> Option<&[u8]> {
+        let region = self.snapshot.find_region(address)?;
+        let offset = address - region.get_start();
+
+        region.get_bytes().get(offset..offset.checked_add(size)?)
+    }
+}
+
+impl MemorySource for SnapshotMemory {
+    fn query(&self, address: usize) -> SnapshotErrOr<MemoryRegion> {
+        let regions = self.snapshot.get_regions();
+        let index = regions.partition_point(|region| region.get_end() <= address);
+
+        Ok(match regions.get(index) {
+            Some(region) if region.contains(address) => {
+                let protection = region.get_protection();
+
+                MemoryRegion::new(
+                    region.get_start(),
+                    region.get_end(),
+                    true,
+                    Protection::new(protection.is_readable(), false, protection.is_executable()),
+                )
+            }
+            // Unmapped gap between neighbouring regions
+            next => MemoryRegion::new(
+                index
+                    .checked_sub(1)
+                    .map_or(0, |previous| regions[previous].get_end()),
+                next.map_or(usize::MAX, |region| region.get_start()),
+                false,
+                Protection::NONE,
+            ),
+        })
+    }
+
+    unsafe fn read_bytes(&self, address: usize, buffer: &mut [u8]) -> SnapshotErrOr<()> {
+        // Reads may span adjacent regions
+        let mut done = 0;
+        while done < buffer.len() {
+            let current = address + done;
+            let region = self
+                .snapshot
+                .find_region(current)
+                .ok_or(Error::AccessViolation)?;
+
+            let offset = current - region.get_start();
+            let size = (buffer.len() - done).min(region.get_bytes().len() - offset);
+
+            buffer[done..done + size].copy_from_slice(&region.get_bytes()[offset..offset + size]);
+            done += size;
+        }
+
+        Ok(())
+    }
+
+    unsafe fn write_bytes(&self, _: usize, _: &[u8]) -> SnapshotErrOr<()> {
+        Err(Error::AccessViolation.into())
+    }
+
+    /// Borrows from the snapshot when within one region, copies otherwise
+    fn view(&self, address: usize, size: usize) -> SnapshotErrOr<Cow<'_, [u8]>> {
+        self.check_access(address, size, false)?;
+
+        Ok(match self.get_bytes(address, size) {
+            Some(bytes) => Cow::Borrowed(bytes),
+            None => {
+                let mut buffer = vec![0; size];
+                unsafe { self.read_bytes(address, &mut buffer)? };
+                Cow::Owned(buffer)
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::source::BufferMemory;
+
+    const BASE: usize = 0x10000;
+    const IMAGE_SIZE: usize = 0x200;
+
+    /// Machine of modules as wide as we are
+    const NATIVE_MACHINE: u16 = if size_of::<usize>() == 8 {
+        0x8664
+    } else {
+        0x14C
+    };
+
+    /// Buffer holding a module image built for `machine` at `BASE`, and a
+    /// page of heap right after
+    fn process(machine: u16) -> (BufferMemory, ModuleInfo) {
+        let mut bytes = vec![0; IMAGE_SIZE + 0x100];
+        bytes[..2].copy_from_slice(b"MZ");
+        bytes[0x3C..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        bytes[0x80..0x84].copy_from_slice(b"PE\0\0");
+        bytes[0x84..0x86].copy_from_slice(&machine.to_le_bytes());
+        bytes[IMAGE_SIZE..IMAGE_SIZE + 4].copy_from_slice(&0xDEADBEEFu32.to_le_bytes());
+
+        let info = ModuleInfo::new(
+            BASE,
+            IMAGE_SIZE,
+            "C:\\tf\\bin\\client.dll".to_owned(),
+            "client.dll".to_owned(),
+        );
+
+        (BufferMemory::new(BASE, bytes, Protection::READ_WRITE), info)
+    }
+
+    fn serialize(snapshot: &Snapshot) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        snapshot.write_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn is_invalid(result: SnapshotErrOr<impl std::fmt::Debug>) -> bool {
+        result
+            .unwrap_err()
+            .downcast_ref::<Error>()
+            .is_some_and(|error| *error == Error::InvalidSnapshot)
+    }
+
+    #[test]
+    fn round_trip() {
+        let (memory, info) = process(NATIVE_MACHINE);
+        let snapshot = Snapshot::capture(&memory, vec![info], &[(BASE + IMAGE_SIZE, 4)]).unwrap();
+
+        assert_eq!(snapshot.get_pointer_width() as usize, size_of::<usize>());
+        assert_eq!(snapshot.get_regions().len(), 1);
+
+        let loaded = Snapshot::read_from(&mut serialize(&snapshot).as_slice()).unwrap();
+        assert!(loaded.get_modules()[0].matches("CLIENT.dll"));
+        assert_eq!(
+            loaded.get_regions()[0].get_range(),
+            snapshot.get_regions()[0].get_range()
+        );
+
+        let replay = SnapshotMemory::try_from(loaded).unwrap();
+        assert_eq!(replay.read::<u32>(BASE + IMAGE_SIZE).unwrap(), 0xDEADBEEF);
+        assert_eq!(replay.read::<[u8; 2]>(BASE).unwrap(), *b"MZ");
+
+        // Past the captured range, and read-only
+        assert!(replay.read::<u8>(BASE + IMAGE_SIZE + 4).is_err());
+        assert!(replay.write(BASE, &0u8).is_err());
+    }
+
+    #[test]
+    fn foreign_pointer_width() {
+        let foreign = if NATIVE_MACHINE == 0x14C {
+            0x8664
+        } else {
+            0x14C
+        };
+        let (memory, info) = process(foreign);
+        let snapshot = Snapshot::capture(&memory, vec![info], &[]).unwrap();
+
+        assert_ne!(snapshot.get_pointer_width() as usize, size_of::<usize>());
+        assert!(!snapshot.is_native());
+
+        // Captures fine, but neither loads nor replays
+        assert!(is_invalid(Snapshot::read_from(
+            &mut serialize(&snapshot).as_slice()
+        )));
+        assert!(is_invalid(SnapshotMemory::try_from(snapshot)));
+    }
+
+    #[test]
+    fn mixed_pointer_widths() {
+        let (memory, info) = process(NATIVE_MACHINE);
+
+        // Second module, of another width, where the heap was
+        let foreign = ModuleInfo::new(BASE + IMAGE_SIZE, 0x100, String::new(), String::new());
+        let foreign_machine: u16 = if NATIVE_MACHINE == 0x14C {
+            0x8664
+        } else {
+            0x14C
+        };
+        memory.write(BASE + IMAGE_SIZE + 0x3C, &0x80u32).unwrap();
+        memory
+            .write(BASE + IMAGE_SIZE + 0x84, &foreign_machine)
+            .unwrap();
+
+        let error = Snapshot::capture(&memory, vec![info, foreign], &[]).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<Error>(),
+            Some(&Error::PointerWidthMismatch)
+        );
+        assert!(Snapshot::capture(&memory, vec![], &[]).is_err());
+    }
+
+    #[test]
+    fn corrupted() {
+        let (memory, info) = process(NATIVE_MACHINE);
+        let bytes = serialize(&Snapshot::capture(&memory, vec![info], &[]).unwrap());
+
+        let corrupt = |index: usize| {
+            let mut bytes = bytes.clone();
+            bytes[index] ^= 0xFF;
+            Snapshot::read_from(&mut bytes.as_slice())
+        };
+
+        // Magic, version, pointer width
+        assert!(is_invalid(corrupt(0)));
+        assert!(is_invalid(corrupt(SNAPSHOT_MAGIC.len())));
+        assert!(is_invalid(corrupt(SNAPSHOT_MAGIC.len() + 4)));
+
+        // Truncated anywhere
+        for length in [0, 8, 13, bytes.len() / 2, bytes.len() - 1] {
+            assert!(Snapshot::read_from(&mut &bytes[..length]).is_err());
+        }
+    }
+}
@@ -0,0 +1,721 @@
+//! Memory source module
+//!
+//! Abstracts where memory is read from and written to, so the same checked
+//! operations, scans and module parsing work on our own process, on another
+//! process, and on plain byte buffers.
+
+#[cfg(windows)]
+use crate::memory::protection::ProtectionGuard;
+use crate::{
+    error::Error,
+    memory::{pod::Pod, range::AddressRange},
+    GenericErrOr,
+};
+#[cfg(windows)]
+use ntapi::{
+    ntpsapi::{NtQueryInformationProcess, ProcessBasicInformation, PROCESS_BASIC_INFORMATION},
+    ntrtl::RtlNtStatusToDosError,
+};
+use std::{
+    borrow::Cow,
+    mem::{size_of, MaybeUninit},
+    path::Path,
+    sync::{Arc, RwLock},
+};
+#[cfg(windows)]
+use std::{io, ptr};
+#[cfg(windows)]
+use winapi::{
+    shared::{
+        minwindef::{BOOL, DWORD, FALSE},
+        ntdef::NT_SUCCESS,
+    },
+    um::{
+        handleapi::CloseHandle,
+        memoryapi::{ReadProcessMemory, VirtualQuery, VirtualQueryEx, WriteProcessMemory},
+        processthreadsapi::{GetCurrentProcess, OpenProcess},
+        winnt::{
+            HANDLE, MEMORY_BASIC_INFORMATION, MEM_COMMIT, PAGE_EXECUTE, PAGE_EXECUTE_READ,
+            PAGE_EXECUTE_READWRITE, PAGE_EXECUTE_WRITECOPY, PAGE_GUARD, PAGE_NOACCESS,
+            PAGE_READONLY, PAGE_READWRITE, PAGE_WRITECOPY, PROCESS_QUERY_INFORMATION,
+            PROCESS_VM_OPERATION, PROCESS_VM_READ, PROCESS_VM_WRITE,
+        },
+        wow64apiset::IsWow64Process,
+    },
+};
+
+/// Type for memory source errors
+pub type MemoryErrOr<T> = GenericErrOr<T>;
+
+/// Page protection, reduced to what we care about
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub struct Protection {
+    read: bool,
+    write: bool,
+    execute: bool,
+}
+
+impl Protection {
+    pub const NONE: Self = Self::new(false, false, false);
+    pub const READ: Self = Self::new(true, false, false);
+    pub const READ_WRITE: Self = Self::new(true, true, false);
+    pub const READ_EXECUTE: Self = Self::new(true, false, true);
+    pub const READ_WRITE_EXECUTE: Self = Self::new(true, true, true);
+
+    pub const fn new(read: bool, write: bool, execute: bool) -> Self {
+        Self {
+            read,
+            write,
+            execute,
+        }
+    }
+
+    /// Get protection from `PAGE_*` constant, guard pages count as inaccessible
+    #[cfg(windows)]
+    pub fn from_page_protect(protect: DWORD) -> Self {
+        if protect & PAGE_GUARD != 0 {
+            return Self::NONE;
+        }
+
+        match protect & 0xFF {
+            PAGE_READONLY => Self::READ,
+            PAGE_READWRITE | PAGE_WRITECOPY => Self::READ_WRITE,
+            PAGE_EXECUTE => Self::new(false, false, true),
+            PAGE_EXECUTE_READ => Self::READ_EXECUTE,
+            PAGE_EXECUTE_READWRITE | PAGE_EXECUTE_WRITECOPY => Self::READ_WRITE_EXECUTE,
+            _ => Self::NONE,
+        }
+    }
+
+    /// Get closest `PAGE_*` constant, write implies read
+    #[cfg(windows)]
+    pub fn to_page_protect(&self) -> DWORD {
+        match (self.read || self.write, self.write, self.execute) {
+            (false, _, false) => PAGE_NOACCESS,
+            (true, false, false) => PAGE_READONLY,
+            (true, true, false) => PAGE_READWRITE,
+            (false, _, true) => PAGE_EXECUTE,
+            (true, false, true) => PAGE_EXECUTE_READ,
+            (true, true, true) => PAGE_EXECUTE_READWRITE,
+        }
+    }
+
+    pub fn is_readable(&self) -> bool {
+        self.read
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.write
+    }
+
+    pub fn is_executable(&self) -> bool {
+        self.execute
+    }
+}
+
+/// Contiguous run of pages sharing state and protection
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct MemoryRegion {
+    start: usize,
+    end: usize,
+    committed: bool,
+    protection: Protection,
+}
+
+#[cfg(windows)]
+impl From<MEMORY_BASIC_INFORMATION> for MemoryRegion {
+    fn from(information: MEMORY_BASIC_INFORMATION) -> Self {
+        let start = information.BaseAddress as usize;
+
+        Self::new(
+            start,
+            start + information.RegionSize,
+            information.State == MEM_COMMIT,
+            Protection::from_page_protect(information.Protect),
+        )
+    }
+}
+
+impl MemoryRegion {
+    pub fn new(start: usize, end: usize, committed: bool, protection: Protection) -> Self {
+        Self {
+            start,
+            end,
+            committed,
+            protection,
+        }
+    }
+
+    /// Get region start address
+    pub fn get_start(&self) -> usize {
+        self.start
+    }
+
+    /// Get region end address
+    pub fn get_end(&self) -> usize {
+        self.end
+    }
+
+    /// Get region size
+    pub fn get_size(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Get addresses region spans
+    pub fn get_range(&self) -> AddressRange {
+        AddressRange::new(self.start, self.end)
+    }
+
+    /// Whether region is backed by memory
+    pub fn is_committed(&self) -> bool {
+        self.committed
+    }
+
+    /// Get region protection, meaningless if not committed
+    pub fn get_protection(&self) -> Protection {
+        self.protection
+    }
+
+    /// Whether region can be read from
+    pub fn is_readable(&self) -> bool {
+        self.committed && self.protection.is_readable()
+    }
+
+    /// Whether region can be written to
+    pub fn is_writable(&self) -> bool {
+        self.committed && self.protection.is_writable()
+    }
+}
+
+/// Somewhere we can read memory from and write memory to, by address
+pub trait MemorySource {
+    /// Query region containing `address`
+    fn query(&self, address: usize) -> MemoryErrOr<MemoryRegion>;
+
+    /// Read `buffer.len()` bytes from `address`, without checking access
+    ///
+    /// # Safety
+    ///
+    /// Callers make sure the range is readable, see `read_bytes_checked`
+    unsafe fn read_bytes(&self, address: usize, buffer: &mut [u8]) -> MemoryErrOr<()>;
+
+    /// Write `buffer` to `address`, without checking access
+    ///
+    /// # Safety
+    ///
+    /// Callers make sure the range is writable, see `write_bytes_checked`
+    unsafe fn write_bytes(&self, address: usize, buffer: &[u8]) -> MemoryErrOr<()>;
+
+    /// Verify `size` bytes from `address` are all committed and readable,
+    /// or writable if `write`
+    fn check_access(&self, address: usize, size: usize, write: bool) -> MemoryErrOr<()> {
+        if address == 0 {
+            return Err(Error::NullPointer.into());
+        }
+
+        let end = address.checked_add(size).ok_or(Error::AccessViolation)?;
+
+        // Walk every region the range touches
+        let mut current = address;
+        while current < end {
+            let region = self.query(current)?;
+            let accessible = if write {
+                region.is_writable()
+            } else {
+                region.is_readable()
+            };
+
+            if !accessible || region.get_end() <= current {
+                return Err(Error::AccessViolation.into());
+            }
+
+            current = region.get_end();
+        }
+
+        Ok(())
+    }
+
+    /// Read `buffer.len()` bytes from `address`, if readable
+    fn read_bytes_checked(&self, address: usize, buffer: &mut [u8]) -> MemoryErrOr<()> {
+        self.check_access(address, buffer.len(), false)?;
+        unsafe { self.read_bytes(address, buffer) }
+    }
+
+    /// Write `buffer` to `address`, if writable
+    fn write_bytes_checked(&self, address: usize, buffer: &[u8]) -> MemoryErrOr<()> {
+        self.check_access(address, buffer.len(), true)?;
+        unsafe { self.write_bytes(address, buffer) }
+    }
+
+    /// Write `buffer` to `address` whatever its protection, for sources that
+    /// can lift it while writing (e.g. to patch code). Same as
+    /// `write_bytes_checked` otherwise
+    fn write_bytes_unprotected(&self, address: usize, buffer: &[u8]) -> MemoryErrOr<()> {
+        self.write_bytes_checked(address, buffer)
+    }
+
+    /// Get `size` bytes from `address`, if readable. Borrowed where the source
+    /// allows it, copied otherwise
+    fn view(&self, address: usize, size: usize) -> MemoryErrOr<Cow<'_, [u8]>> {
+        let mut buffer = vec![0; size];
+        self.read_bytes_checked(address, &mut buffer)?;
+        Ok(Cow::Owned(buffer))
+    }
+
+    /// Read null-terminated string at `address`, up to `max_length` bytes, if readable
+    ///
+    /// Stops at the end of the region `address` lies in, so strings crossing regions
+    /// are reported as missing their terminator.
+    fn read_c_string(&self, address: usize, max_length: usize) -> MemoryErrOr<String> {
+        let region = self.query(address)?;
+        let size = max_length.min(region.get_end().saturating_sub(address));
+
+        let bytes = self.view(address, size)?;
+        let length = bytes
+            .iter()
+            .position(|&x| x == b'\0')
+            .ok_or(Error::NoSentinelCharacter)?;
+
+        Ok(String::from_utf8_lossy(&bytes[..length]).into_owned())
+    }
+
+    /// Read `T` from `address`, if readable
+    fn read<T: Pod>(&self, address: usize) -> MemoryErrOr<T>
+    where
+        Self: Sized,
+    {
+        unsafe { self.read_unchecked(address) }
+    }
+
+    /// Read `T` from `address`, if readable, for types that can't be `Pod`
+    ///
+    /// # Safety
+    ///
+    /// Copies `size_of::<T>()` bytes, so `T` has to be valid for any bit pattern
+    /// (integers, pointers, `#[repr(C)]` structures of those...). Unlike `Pod`,
+    /// padding is fine
+    unsafe fn read_unchecked<T: Copy>(&self, address: usize) -> MemoryErrOr<T>
+    where
+        Self: Sized,
+    {
+        let mut value = MaybeUninit::<T>::uninit();
+        let buffer = unsafe {
+            std::slice::from_raw_parts_mut(value.as_mut_ptr() as *mut u8, size_of::<T>())
+        };
+
+        self.read_bytes_checked(address, buffer)?;
+        Ok(unsafe { value.assume_init() })
+    }
+
+    /// Write `value` to `address`, if writable
+    fn write<T: Pod>(&self, address: usize, value: &T) -> MemoryErrOr<()>
+    where
+        Self: Sized,
+    {
+        let buffer =
+            unsafe { std::slice::from_raw_parts(value as *const T as *const u8, size_of::<T>()) };
+
+        self.write_bytes_checked(address, buffer)
+    }
+}
+
+/// Memory of our own process
+#[cfg(windows)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalMemory;
+
+#[cfg(windows)]
+impl MemorySource for LocalMemory {
+    fn query(&self, address: usize) -> MemoryErrOr<MemoryRegion> {
+        let mut information = MaybeUninit::<MEMORY_BASIC_INFORMATION>::uninit();
+
+        let written = unsafe {
+            VirtualQuery(
+                address as _,
+                information.as_mut_ptr(),
+                size_of::<MEMORY_BASIC_INFORMATION>(),
+            )
+        };
+
+        if written == 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        Ok(MemoryRegion::from(unsafe { information.assume_init() }))
+    }
+
+    unsafe fn read_bytes(&self, address: usize, buffer: &mut [u8]) -> MemoryErrOr<()> {
+        std::ptr::copy_nonoverlapping(address as *const u8, buffer.as_mut_ptr(), buffer.len());
+        Ok(())
+    }
+
+    unsafe fn write_bytes(&self, address: usize, buffer: &[u8]) -> MemoryErrOr<()> {
+        std::ptr::copy_nonoverlapping(buffer.as_ptr(), address as *mut u8, buffer.len());
+        Ok(())
+    }
+
+    /// Borrows memory directly, no copy
+    fn view(&self, address: usize, size: usize) -> MemoryErrOr<Cow<'_, [u8]>> {
+        self.check_access(address, size, false)?;
+        Ok(Cow::Borrowed(unsafe {
+            std::slice::from_raw_parts(address as *const u8, size)
+        }))
+    }
+
+    /// Makes the range writable for the duration of the write
+    fn write_bytes_unprotected(&self, address: usize, buffer: &[u8]) -> MemoryErrOr<()> {
+        let _guard = ProtectionGuard::writable(AddressRange::with_size(address, buffer.len())?)?;
+        self.write_bytes_checked(address, buffer)
+    }
+}
+
+/// Owned process handle, closed on drop
+#[cfg(windows)]
+#[derive(Debug)]
+struct ProcessHandle(HANDLE);
+
+// Process handles can be used from any thread
+#[cfg(windows)]
+unsafe impl Send for ProcessHandle {}
+#[cfg(windows)]
+unsafe impl Sync for ProcessHandle {}
+
+#[cfg(windows)]
+impl Drop for ProcessHandle {
+    fn drop(&mut self) {
+        unsafe { CloseHandle(self.0) };
+    }
+}
+
+/// Memory of another process, through `ReadProcessMemory`/`WriteProcessMemory`
+///
+/// Cheap to clone, clones share the same handle.
+#[cfg(windows)]
+#[derive(Debug, Clone)]
+pub struct ProcessMemory {
+    handle: Arc<ProcessHandle>,
+}
+
+#[cfg(windows)]
+impl ProcessMemory {
+    /// Open process with id `process_id` for reading, writing and querying memory
+    pub fn open(process_id: DWORD) -> MemoryErrOr<Self> {
+        let handle = unsafe {
+            OpenProcess(
+                PROCESS_VM_READ
+                    | PROCESS_VM_WRITE
+                    | PROCESS_VM_OPERATION
+                    | PROCESS_QUERY_INFORMATION,
+                FALSE,
+                process_id,
+            )
+        };
+
+        if handle.is_null() {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        Ok(Self {
+            handle: Arc::new(ProcessHandle(handle)),
+        })
+    }
+
+    /// Get raw process handle, stays owned by us
+    pub fn get_handle(&self) -> HANDLE {
+        self.handle.0
+    }
+
+    /// Get size of process' pointers, in bytes
+    pub fn get_pointer_width(&self) -> MemoryErrOr<usize> {
+        let is_wow64 = |handle| {
+            let mut result: BOOL = FALSE;
+            match unsafe { IsWow64Process(handle, &mut result) } {
+                FALSE => Err(io::Error::last_os_error()),
+                _ => Ok(result != FALSE),
+            }
+        };
+
+        // WOW64 processes are 32-bit, others match the OS. We're either 64-bit
+        // ourselves, or 32-bit on a 64-bit OS exactly when we're WOW64 too
+        Ok(if is_wow64(self.get_handle())? {
+            4
+        } else if cfg!(target_pointer_width = "64") || is_wow64(unsafe { GetCurrentProcess() })? {
+            8
+        } else {
+            4
+        })
+    }
+
+    /// Get process' PEB address, for walking its loader list
+    ///
+    /// Refuses processes whose pointers aren't as wide as ours, we'd find
+    /// the PEB of our own bitness, laid out and listing modules differently.
+    pub fn get_peb(&self) -> MemoryErrOr<usize> {
+        if self.get_pointer_width()? != size_of::<usize>() {
+            return Err(Error::PointerWidthMismatch.into());
+        }
+
+        let mut information = MaybeUninit::<PROCESS_BASIC_INFORMATION>::uninit();
+
+        let status = unsafe {
+            NtQueryInformationProcess(
+                self.get_handle(),
+                ProcessBasicInformation,
+                information.as_mut_ptr() as _,
+                size_of::<PROCESS_BASIC_INFORMATION>() as _,
+                ptr::null_mut(),
+            )
+        };
+
+        if !NT_SUCCESS(status) {
+            return Err(
+                io::Error::from_raw_os_error(unsafe { RtlNtStatusToDosError(status) } as _).into(),
+            );
+        }
+
+        Ok(unsafe { information.assume_init() }.PebBaseAddress as usize)
+    }
+}
+
+#[cfg(windows)]
+impl MemorySource for ProcessMemory {
+    fn query(&self, address: usize) -> MemoryErrOr<MemoryRegion> {
+        let mut information = MaybeUninit::<MEMORY_BASIC_INFORMATION>::uninit();
+
+        let written = unsafe {
+            VirtualQueryEx(
+                self.get_handle(),
+                address as _,
+                information.as_mut_ptr(),
+                size_of::<MEMORY_BASIC_INFORMATION>(),
+            )
+        };
+
+        if written == 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        Ok(MemoryRegion::from(unsafe { information.assume_init() }))
+    }
+
+    unsafe fn read_bytes(&self, address: usize, buffer: &mut [u8]) -> MemoryErrOr<()> {
+        let mut read = 0;
+        if ReadProcessMemory(
+            self.get_handle(),
+            address as _,
+            buffer.as_mut_ptr() as _,
+            buffer.len(),
+            &mut read,
+        ) == 0
+        {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        if read != buffer.len() {
+            return Err(Error::AccessViolation.into());
+        }
+
+        Ok(())
+    }
+
+    unsafe fn write_bytes(&self, address: usize, buffer: &[u8]) -> MemoryErrOr<()> {
+        let mut written = 0;
+        if WriteProcessMemory(
+            self.get_handle(),
+            address as _,
+            buffer.as_ptr() as _,
+            buffer.len(),
+            &mut written,
+        ) == 0
+        {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        if written != buffer.len() {
+            return Err(Error::AccessViolation.into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Byte buffer pretending to live at `base`, everything else is unmapped
+///
+/// Cheap to clone, clones share the same bytes.
+#[derive(Debug, Clone)]
+pub struct BufferMemory {
+    base: usize,
+    protection: Protection,
+    bytes: Arc<RwLock<Vec<u8>>>,
+}
+
+impl BufferMemory {
+    pub fn new(base: usize, bytes: Vec<u8>, protection: Protection) -> Self {
+        Self {
+            base,
+            protection,
+            bytes: Arc::new(RwLock::new(bytes)),
+        }
+    }
+
+    /// Load raw bytes from file at `path`, pretending they live at `base`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use shared::memory::{
+    /// #     module::Module,
+    /// #     source::{BufferMemory, Protection},
+    /// # };
+    /// // Dumped .text of client.dll
+    /// let text = BufferMemory::from_file("client_text.bin", 0x10001000, Protection::READ_EXECUTE)?;
+    /// let client = Module::new_with_source(text.clone(), text.get_base(), text.get_end());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn from_file(
+        path: impl AsRef<Path>,
+        base: usize,
+        protection: Protection,
+    ) -> MemoryErrOr<Self> {
+        Ok(Self::new(base, std::fs::read(path)?, protection))
+    }
+
+    /// Get address buffer is mapped at
+    pub fn get_base(&self) -> usize {
+        self.base
+    }
+
+    /// Get address right past the buffer
+    pub fn get_end(&self) -> usize {
+        self.base + self.bytes.read().unwrap().len()
+    }
+
+    /// Get copy of underlying bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.bytes.read().unwrap().clone()
+    }
+
+    /// Get offset of `size` bytes at `address` into buffer, if they're all within it
+    fn get_offset(&self, address: usize, size: usize) -> MemoryErrOr<usize> {
+        let length = self.bytes.read().unwrap().len();
+
+        address
+            .checked_sub(self.base)
+            .filter(|offset| offset.checked_add(size).is_some_and(|end| end <= length))
+            .ok_or_else(|| Error::AccessViolation.into())
+    }
+}
+
+impl MemorySource for BufferMemory {
+    fn query(&self, address: usize) -> MemoryErrOr<MemoryRegion> {
+        let end = self.get_end();
+
+        // Unmapped gaps on either side of the buffer
+        Ok(if address < self.base {
+            MemoryRegion::new(0, self.base, false, Protection::NONE)
+        } else if address >= end {
+            MemoryRegion::new(end, usize::MAX, false, Protection::NONE)
+        } else {
+            MemoryRegion::new(self.base, end, true, self.protection)
+        })
+    }
+
+    unsafe fn read_bytes(&self, address: usize, buffer: &mut [u8]) -> MemoryErrOr<()> {
+        let offset = self.get_offset(address, buffer.len())?;
+        buffer.copy_from_slice(&self.bytes.read().unwrap()[offset..offset + buffer.len()]);
+        Ok(())
+    }
+
+    unsafe fn write_bytes(&self, address: usize, buffer: &[u8]) -> MemoryErrOr<()> {
+        let offset = self.get_offset(address, buffer.len())?;
+        self.bytes.write().unwrap()[offset..offset + buffer.len()].copy_from_slice(buffer);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: usize = 0x1000;
+
+    fn buffer(protection: Protection) -> BufferMemory {
+        BufferMemory::new(BASE, (0..0x20).collect(), protection)
+    }
+
+    #[test]
+    fn query() {
+        let memory = buffer(Protection::READ);
+
+        let below = memory.query(BASE - 1).unwrap();
+        assert_eq!((below.get_start(), below.get_end()), (0, BASE));
+        assert!(!below.is_committed() && !below.is_readable());
+
+        let inside = memory.query(BASE + 0x1F).unwrap();
+        assert_eq!((inside.get_start(), inside.get_end()), (BASE, BASE + 0x20));
+        assert!(inside.is_readable() && !inside.is_writable());
+
+        let above = memory.query(BASE + 0x20).unwrap();
+        assert_eq!(
+            (above.get_start(), above.get_end()),
+            (BASE + 0x20, usize::MAX)
+        );
+        assert!(!above.is_readable());
+    }
+
+    #[test]
+    fn read() {
+        let memory = buffer(Protection::READ);
+
+        assert_eq!(memory.read::<u8>(BASE).unwrap(), 0);
+        assert_eq!(memory.read::<u32>(BASE + 1).unwrap(), 0x04030201);
+        assert_eq!(memory.read::<[u8; 2]>(BASE + 0x1E).unwrap(), [0x1E, 0x1F]);
+
+        // Straddling either end, or outside
+        assert!(memory.read::<u16>(BASE - 1).is_err());
+        assert!(memory.read::<u16>(BASE + 0x1F).is_err());
+        assert!(memory.read::<u8>(BASE + 0x20).is_err());
+        assert!(memory.read::<u8>(0).is_err());
+        assert!(memory.read::<u8>(usize::MAX).is_err());
+    }
+
+    #[test]
+    fn write() {
+        let memory = buffer(Protection::READ_WRITE);
+
+        memory.write(BASE + 2, &0xDEADBEEFu32).unwrap();
+        assert_eq!(memory.read::<u32>(BASE + 2).unwrap(), 0xDEADBEEF);
+        assert_eq!(memory.to_bytes()[..7], [0, 1, 0xEF, 0xBE, 0xAD, 0xDE, 6]);
+
+        // Clones share bytes
+        let clone = memory.clone();
+        clone.write(BASE, &0xFFu8).unwrap();
+        assert_eq!(memory.read::<u8>(BASE).unwrap(), 0xFF);
+
+        assert!(memory.write(BASE + 0x1F, &0u16).is_err());
+        assert!(buffer(Protection::READ).write(BASE, &0u8).is_err());
+    }
+
+    #[test]
+    fn check_access() {
+        let memory = buffer(Protection::READ_EXECUTE);
+
+        assert!(memory.check_access(BASE, 0x20, false).is_ok());
+        assert!(memory.check_access(BASE, 0x21, false).is_err());
+        assert!(memory.check_access(BASE, 1, true).is_err());
+        assert!(memory.check_access(0, 1, false).is_err());
+        assert!(memory.check_access(usize::MAX, 2, false).is_err());
+    }
+
+    #[test]
+    fn read_c_string() {
+        let memory = BufferMemory::new(BASE, b"client\0engine".to_vec(), Protection::READ);
+
+        assert_eq!(memory.read_c_string(BASE, 64).unwrap(), "client");
+        assert_eq!(memory.read_c_string(BASE + 3, 64).unwrap(), "ent");
+
+        // Too short a limit, and running off the end of the buffer
+        assert!(memory.read_c_string(BASE, 3).is_err());
+        assert!(memory.read_c_string(BASE + 7, 64).is_err());
+    }
+}
@@ -0,0 +1,854 @@
+//! x86 instruction length decoder module
+//!
+//! Only decodes as much as is needed to know instruction boundaries and where
+//! operands live (immediates, displacements, relative targets), it doesn't
+//! attempt to produce mnemonics.
+
+use crate::{error::Error, GenericErrOr};
+
+/// Type for x86 decoding errors
+pub type X86ErrOr<T> = GenericErrOr<T>;
+
+/// Architectural limit for a single instruction's length
+pub const MAX_INSTRUCTION_LENGTH: usize = 15;
+
+// Opcode table flags
+const NONE: u16 = 0;
+/// Has ModRM byte
+const MODRM: u16 = 1 << 0;
+/// Has 8-bit immediate
+const IMM8: u16 = 1 << 1;
+/// Has 16-bit immediate
+const IMM16: u16 = 1 << 2;
+/// Has 16/32-bit immediate, depending on operand size
+const IMMZ: u16 = 1 << 3;
+/// Has 16/32/64-bit immediate, depending on operand size
+const IMMV: u16 = 1 << 4;
+/// Has 8-bit relative target
+const REL8: u16 = 1 << 5;
+/// Has 16/32-bit relative target, depending on operand size
+const RELZ: u16 = 1 << 6;
+/// Has memory offset sized by address size (`mov eax, [moffs]`)
+const MOFFS: u16 = 1 << 7;
+/// Has far pointer (`ptr16:16`/`ptr16:32`)
+const FAR: u16 = 1 << 8;
+/// Group 3 (`F6`/`F7`), only `/0` and `/1` carry an immediate
+const GROUP3: u16 = 1 << 9;
+/// Not encodable in 64-bit mode
+const INVALID64: u16 = 1 << 10;
+/// Not encodable in any mode
+const INVALID: u16 = 1 << 11;
+/// Opcode escape (`0F`, `0F 38`, `0F 3A`)
+const ESCAPE: u16 = 1 << 12;
+/// Legacy prefix, handled before table lookup
+const PREFIX: u16 = 1 << 13;
+
+// Shorthands so the tables stay readable
+const M: u16 = MODRM;
+const I8: u16 = IMM8;
+const IW: u16 = IMM16;
+const IZ: u16 = IMMZ;
+const IV: u16 = IMMV;
+const R8: u16 = REL8;
+const RZ: u16 = RELZ;
+const MO: u16 = MOFFS;
+const FP: u16 = FAR;
+const G3: u16 = GROUP3;
+const N: u16 = NONE;
+const X64: u16 = INVALID64;
+const UD: u16 = INVALID;
+const P: u16 = PREFIX;
+const ESC: u16 = ESCAPE;
+
+/// One-byte opcode map
+#[rustfmt::skip]
+const ONE_BYTE: [u16; 256] = [
+    //      0             1             2             3             4             5             6             7             8             9             A             B             C             D             E             F
+    /* 0 */ M,            M,            M,            M,            I8,           IZ,           X64,          X64,          M,            M,            M,            M,            I8,           IZ,           X64,          ESC,
+    /* 1 */ M,            M,            M,            M,            I8,           IZ,           X64,          X64,          M,            M,            M,            M,            I8,           IZ,           X64,          X64,
+    /* 2 */ M,            M,            M,            M,            I8,           IZ,           P,            X64,          M,            M,            M,            M,            I8,           IZ,           P,            X64,
+    /* 3 */ M,            M,            M,            M,            I8,           IZ,           P,            X64,          M,            M,            M,            M,            I8,           IZ,           P,            X64,
+    /* 4 */ N,            N,            N,            N,            N,            N,            N,            N,            N,            N,            N,            N,            N,            N,            N,            N,
+    /* 5 */ N,            N,            N,            N,            N,            N,            N,            N,            N,            N,            N,            N,            N,            N,            N,            N,
+    /* 6 */ X64,          X64,          M | X64,      M,            P,            P,            P,            P,            IZ,           M | IZ,       I8,           M | I8,       N,            N,            N,            N,
+    /* 7 */ R8,           R8,           R8,           R8,           R8,           R8,           R8,           R8,           R8,           R8,           R8,           R8,           R8,           R8,           R8,           R8,
+    /* 8 */ M | I8,       M | IZ,       M | I8 | X64, M | I8,       M,            M,            M,            M,            M,            M,            M,            M,            M,            M,            M,            M,
+    /* 9 */ N,            N,            N,            N,            N,            N,            N,            N,            N,            N,            FP | X64,     N,            N,            N,            N,            N,
+    /* A */ MO,           MO,           MO,           MO,           N,            N,            N,            N,            I8,           IZ,           N,            N,            N,            N,            N,            N,
+    /* B */ I8,           I8,           I8,           I8,           I8,           I8,           I8,           I8,           IV,           IV,           IV,           IV,           IV,           IV,           IV,           IV,
+    /* C */ M | I8,       M | I8,       IW,           N,            M | X64,      M | X64,      M | I8,       M | IZ,       IW | I8,      N,            IW,           N,            N,            I8,           X64,          N,
+    /* D */ M,            M,            M,            M,            I8 | X64,     I8 | X64,     X64,          N,            M,            M,            M,            M,            M,            M,            M,            M,
+    /* E */ R8,           R8,           R8,           R8,           I8,           I8,           I8,           I8,           RZ,           RZ,           FP | X64,     R8,           N,            N,            N,            N,
+    /* F */ P,            N,            P,            P,            N,            N,            M | G3,       M | G3,       N,            N,            N,            N,            N,            N,            M,            M,
+];
+
+/// Two-byte opcode map (`0F xx`)
+#[rustfmt::skip]
+const TWO_BYTE: [u16; 256] = [
+    //      0       1       2       3       4       5       6       7       8       9       A       B       C       D       E       F
+    /* 0 */ M,      M,      M,      M,      UD,     N,      N,      N,      N,      N,      UD,     N,      UD,     M,      N,      M | I8,
+    /* 1 */ M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,
+    /* 2 */ M,      M,      M,      M,      UD,     UD,     UD,     UD,     M,      M,      M,      M,      M,      M,      M,      M,
+    /* 3 */ N,      N,      N,      N,      N,      N,      N,      N,      ESC,    UD,     ESC,    UD,     UD,     UD,     UD,     UD,
+    /* 4 */ M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,
+    /* 5 */ M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,
+    /* 6 */ M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,
+    /* 7 */ M | I8, M | I8, M | I8, M | I8, M,      M,      M,      N,      M,      M,      M,      M,      M,      M,      M,      M,
+    /* 8 */ RZ,     RZ,     RZ,     RZ,     RZ,     RZ,     RZ,     RZ,     RZ,     RZ,     RZ,     RZ,     RZ,     RZ,     RZ,     RZ,
+    /* 9 */ M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,
+    /* A */ N,      N,      N,      M,      M | I8, M,      UD,     UD,     N,      N,      N,      M,      M | I8, M,      M,      M,
+    /* B */ M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M | I8, M,      M,      M,      M,      M,
+    /* C */ M,      M,      M | I8, M,      M | I8, M | I8, M | I8, M,      N,      N,      N,      N,      N,      N,      N,      N,
+    /* D */ M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,
+    /* E */ M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,
+    /* F */ M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,      M,
+];
+
+/// Decoding mode
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Mode {
+    /// 32-bit protected mode
+    Bits32,
+    /// 64-bit long mode
+    Bits64,
+}
+
+impl Mode {
+    /// Mode matching the architecture we're compiled for
+    pub const fn native() -> Self {
+        if cfg!(target_pointer_width = "64") {
+            Mode::Bits64
+        } else {
+            Mode::Bits32
+        }
+    }
+}
+
+/// Opcode map an instruction's opcode byte belongs to
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OpcodeMap {
+    /// `xx`
+    OneByte,
+    /// `0F xx`
+    TwoByte,
+    /// `0F 38 xx`
+    ThreeByte38,
+    /// `0F 3A xx`
+    ThreeByte3A,
+}
+
+/// What an operand's bytes mean
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OperandKind {
+    /// Constant encoded in the instruction
+    Immediate,
+    /// Memory displacement (or absolute memory offset, for `moffs` forms)
+    Displacement,
+    /// Branch target relative to the end of the instruction
+    Relative,
+}
+
+/// Operand encoded in an instruction's bytes
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Operand {
+    kind: OperandKind,
+    offset: usize,
+    size: usize,
+    value: u64,
+}
+
+impl Operand {
+    /// Get operand kind
+    pub fn get_kind(&self) -> OperandKind {
+        self.kind
+    }
+
+    /// Get operand offset from instruction start
+    pub fn get_offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Get operand size in bytes
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    /// Get operand value, zero-extended
+    pub fn get_value(&self) -> u64 {
+        self.value
+    }
+
+    /// Get operand value, sign-extended from `size` bytes
+    pub fn get_signed_value(&self) -> i64 {
+        let shift = 64 - self.size as u32 * 8;
+        ((self.value << shift) as i64) >> shift
+    }
+}
+
+/// Decoded instruction
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Instruction {
+    mode: Mode,
+    length: usize,
+    map: OpcodeMap,
+    opcode: u8,
+    rex: Option<u8>,
+    modrm: Option<u8>,
+    sib: Option<u8>,
+    rip_relative: bool,
+    displacement: Option<Operand>,
+    immediate: Option<Operand>,
+    second_immediate: Option<Operand>,
+    relative: Option<Operand>,
+}
+
+/// Read-only cursor over the bytes being decoded
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn peek(&self) -> X86ErrOr<u8> {
+        self.bytes
+            .get(self.position)
+            .copied()
+            .ok_or_else(|| Error::InvalidInstruction.into())
+    }
+
+    fn next(&mut self) -> X86ErrOr<u8> {
+        let byte = self.peek()?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    /// Read `size` bytes little endian as an operand of `kind`
+    fn operand(&mut self, kind: OperandKind, size: usize) -> X86ErrOr<Operand> {
+        let offset = self.position;
+        let bytes = self
+            .bytes
+            .get(offset..offset + size)
+            .ok_or(Error::InvalidInstruction)?;
+
+        self.position += size;
+
+        Ok(Operand {
+            kind,
+            offset,
+            size,
+            value: bytes
+                .iter()
+                .rev()
+                .fold(0u64, |value, &byte| (value << 8) | byte as u64),
+        })
+    }
+}
+
+/// Decode instruction at start of `bytes`
+///
+/// # Examples
+///
+/// ```rust
+/// # use shared::memory::x86::{decode, Mode};
+/// // call rel32
+/// let instruction = decode(&[0xE8, 0x10, 0x00, 0x00, 0x00], Mode::Bits32)?;
+/// assert_eq!(instruction.get_length(), 5);
+/// assert_eq!(instruction.relative_target(0x1000), Some(0x1015));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn decode(bytes: &[u8], mode: Mode) -> X86ErrOr<Instruction> {
+    let mut cursor = Cursor { bytes, position: 0 };
+    let mut operand_size_override = false;
+    let mut address_size_override = false;
+    let mut rex = None;
+
+    // Legacy prefixes and REX, REX only counts if it's the last prefix
+    loop {
+        match cursor.peek()? {
+            0x66 => operand_size_override = true,
+            0x67 => address_size_override = true,
+            0xF0 | 0xF2 | 0xF3 | 0x26 | 0x2E | 0x36 | 0x3E | 0x64 | 0x65 => {}
+            byte @ 0x40..=0x4F if mode == Mode::Bits64 => {
+                cursor.position += 1;
+                rex = Some(byte);
+                continue;
+            }
+            _ => break,
+        }
+
+        cursor.position += 1;
+        rex = None;
+    }
+
+    let rex_w = rex.is_some_and(|rex| rex & 0x08 != 0);
+
+    // Opcode, through escapes or VEX
+    let first = cursor.next()?;
+    let (map, opcode, flags) = match first {
+        0xC4 | 0xC5
+            if mode == Mode::Bits64 || cursor.peek().is_ok_and(|byte| byte & 0xC0 == 0xC0) =>
+        {
+            // Two-byte VEX implies `0F`, three-byte VEX selects the map
+            let map = if first == 0xC5 {
+                cursor.next()?;
+                OpcodeMap::TwoByte
+            } else {
+                let map = match cursor.next()? & 0x1F {
+                    1 => OpcodeMap::TwoByte,
+                    2 => OpcodeMap::ThreeByte38,
+                    3 => OpcodeMap::ThreeByte3A,
+                    _ => return Err(Error::InvalidInstruction.into()),
+                };
+                cursor.next()?;
+                map
+            };
+
+            let opcode = cursor.next()?;
+            (map, opcode, map_flags(map, opcode))
+        }
+        0x0F => {
+            let second = cursor.next()?;
+            match second {
+                0x38 | 0x3A => {
+                    let map = if second == 0x38 {
+                        OpcodeMap::ThreeByte38
+                    } else {
+                        OpcodeMap::ThreeByte3A
+                    };
+                    let opcode = cursor.next()?;
+                    (map, opcode, map_flags(map, opcode))
+                }
+                _ => (OpcodeMap::TwoByte, second, TWO_BYTE[second as usize]),
+            }
+        }
+        _ => (OpcodeMap::OneByte, first, ONE_BYTE[first as usize]),
+    };
+
+    if flags & INVALID != 0 || (mode == Mode::Bits64 && flags & INVALID64 != 0) {
+        return Err(Error::InvalidInstruction.into());
+    }
+
+    // Operand and address sizes in bytes
+    let operand_size = if rex_w {
+        8
+    } else if operand_size_override {
+        2
+    } else {
+        4
+    };
+    let address_size = match (mode, address_size_override) {
+        (Mode::Bits32, false) => 4,
+        (Mode::Bits32, true) => 2,
+        (Mode::Bits64, false) => 8,
+        (Mode::Bits64, true) => 4,
+    };
+
+    let mut instruction = Instruction {
+        mode,
+        length: 0,
+        map,
+        opcode,
+        rex,
+        modrm: None,
+        sib: None,
+        rip_relative: false,
+        displacement: None,
+        immediate: None,
+        second_immediate: None,
+        relative: None,
+    };
+
+    let mut flags = flags;
+    if flags & MODRM != 0 {
+        let modrm = cursor.next()?;
+        let (mod_, rm) = (modrm >> 6, modrm & 7);
+        instruction.modrm = Some(modrm);
+
+        // Group 3 `test` forms take an immediate, everything else doesn't
+        if flags & GROUP3 != 0 && (modrm >> 3) & 7 < 2 {
+            flags |= if opcode == 0xF6 { IMM8 } else { IMMZ };
+        }
+
+        let displacement_size = if address_size == 2 {
+            // 16-bit addressing has no SIB
+            match (mod_, rm) {
+                (0, 6) | (2, _) => 2,
+                (1, _) => 1,
+                _ => 0,
+            }
+        } else {
+            let mut displacement_size = match mod_ {
+                1 => 1,
+                2 => 4,
+                _ => 0,
+            };
+
+            if mod_ != 3 && rm == 4 {
+                let sib = cursor.next()?;
+                instruction.sib = Some(sib);
+
+                // No base register, disp32 instead
+                if mod_ == 0 && sib & 7 == 5 {
+                    displacement_size = 4;
+                }
+            } else if mod_ == 0 && rm == 5 {
+                displacement_size = 4;
+                instruction.rip_relative = mode == Mode::Bits64;
+            }
+
+            displacement_size
+        };
+
+        if displacement_size != 0 {
+            instruction.displacement =
+                Some(cursor.operand(OperandKind::Displacement, displacement_size)?);
+        }
+    }
+
+    if flags & MOFFS != 0 {
+        instruction.displacement = Some(cursor.operand(OperandKind::Displacement, address_size)?);
+    }
+
+    // Immediates, in encoding order
+    let mut immediates = [None; 2];
+    let mut sizes = [
+        (IMM16, 2),
+        (IMMZ, operand_size.min(4)),
+        (IMMV, operand_size),
+        (FAR, operand_size.min(4) + 2),
+        (IMM8, 1),
+    ]
+    .into_iter()
+    .filter(|&(flag, _)| flags & flag != 0)
+    .map(|(_, size)| size);
+
+    for immediate in immediates.iter_mut() {
+        if let Some(size) = sizes.next() {
+            *immediate = Some(cursor.operand(OperandKind::Immediate, size)?);
+        }
+    }
+
+    [instruction.immediate, instruction.second_immediate] = immediates;
+
+    if flags & REL8 != 0 {
+        instruction.relative = Some(cursor.operand(OperandKind::Relative, 1)?);
+    } else if flags & RELZ != 0 {
+        // Operand size override only shortens relative targets in 32-bit mode
+        let size = if mode == Mode::Bits32 && operand_size_override {
+            2
+        } else {
+            4
+        };
+        instruction.relative = Some(cursor.operand(OperandKind::Relative, size)?);
+    }
+
+    if cursor.position > MAX_INSTRUCTION_LENGTH {
+        return Err(Error::InvalidInstruction.into());
+    }
+
+    instruction.length = cursor.position;
+    Ok(instruction)
+}
+
+/// Flags for opcode `opcode` in map `map`
+fn map_flags(map: OpcodeMap, opcode: u8) -> u16 {
+    match map {
+        OpcodeMap::OneByte => ONE_BYTE[opcode as usize],
+        OpcodeMap::TwoByte => TWO_BYTE[opcode as usize],
+        OpcodeMap::ThreeByte38 => MODRM,
+        OpcodeMap::ThreeByte3A => MODRM | IMM8,
+    }
+}
+
+/// Get the length of the smallest run of whole instructions at the start of
+/// `bytes` that covers at least `minimum` bytes
+///
+/// # Examples
+///
+/// ```rust
+/// # use shared::memory::x86::{length_covering, Mode};
+/// // push ebp; mov ebp, esp; sub esp, 0x10 -> need 6 bytes to fit a 5 byte jump
+/// let length = length_covering(&[0x55, 0x8B, 0xEC, 0x83, 0xEC, 0x10], Mode::Bits32, 5)?;
+/// assert_eq!(length, 6);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn length_covering(bytes: &[u8], mode: Mode, minimum: usize) -> X86ErrOr<usize> {
+    let mut length = 0;
+    while length < minimum {
+        length += decode(bytes.get(length..).unwrap_or_default(), mode)?.get_length();
+    }
+
+    Ok(length)
+}
+
+impl Instruction {
+    /// Get mode instruction was decoded in
+    pub fn get_mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Get instruction length in bytes
+    pub fn get_length(&self) -> usize {
+        self.length
+    }
+
+    /// Get map opcode belongs to
+    pub fn get_map(&self) -> OpcodeMap {
+        self.map
+    }
+
+    /// Get opcode byte, within its map
+    pub fn get_opcode(&self) -> u8 {
+        self.opcode
+    }
+
+    /// Get REX prefix, if any
+    pub fn get_rex(&self) -> Option<u8> {
+        self.rex
+    }
+
+    /// Get ModRM byte, if any
+    pub fn get_modrm(&self) -> Option<u8> {
+        self.modrm
+    }
+
+    /// Get SIB byte, if any
+    pub fn get_sib(&self) -> Option<u8> {
+        self.sib
+    }
+
+    /// Get memory displacement (or absolute offset), if any
+    pub fn get_displacement(&self) -> Option<Operand> {
+        self.displacement
+    }
+
+    /// Get first immediate, if any
+    pub fn get_immediate(&self) -> Option<Operand> {
+        self.immediate
+    }
+
+    /// Get second immediate, only `enter` has one
+    pub fn get_second_immediate(&self) -> Option<Operand> {
+        self.second_immediate
+    }
+
+    /// Get relative branch operand, if any
+    pub fn get_relative(&self) -> Option<Operand> {
+        self.relative
+    }
+
+    /// Whether the memory operand is relative to the next instruction (64-bit only)
+    pub fn is_rip_relative(&self) -> bool {
+        self.rip_relative
+    }
+
+    /// Iterate all operands, in encoding order
+    pub fn operands(&self) -> impl Iterator<Item = Operand> {
+        [
+            self.displacement,
+            self.immediate,
+            self.second_immediate,
+            self.relative,
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    /// Resolve relative branch target, given the instruction is at `address`
+    pub fn relative_target(&self, address: usize) -> Option<usize> {
+        self.relative
+            .map(|relative| self.resolve(address, relative))
+    }
+
+    /// Resolve RIP-relative memory operand, given the instruction is at `address`
+    pub fn rip_relative_target(&self, address: usize) -> Option<usize> {
+        self.displacement
+            .filter(|_| self.rip_relative)
+            .map(|displacement| self.resolve(address, displacement))
+    }
+
+    /// Resolve `operand` relative to the end of the instruction at `address`
+    fn resolve(&self, address: usize, operand: Operand) -> usize {
+        let target = address
+            .wrapping_add(self.length)
+            .wrapping_add_signed(operand.get_signed_value() as isize);
+
+        // 16-bit relative targets wrap within the low word
+        if operand.size == 2 {
+            target & 0xFFFF
+        } else {
+            target
+        }
+    }
+}
+
+/// Linear sweep over `bytes`, as if they were at `address`
+///
+/// # Examples
+///
+/// ```rust
+/// # use shared::memory::x86::{Decoder, Mode};
+/// # let (bytes, base) = ([0xE8, 0x10, 0x00, 0x00, 0x00], 0x1000);
+/// for (address, instruction) in Decoder::new(&bytes, Mode::native(), base) {
+///     if let Some(target) = instruction.relative_target(address) {
+///         println!("{:#x} -> {:#x}", address, target);
+///     }
+/// }
+/// ```
+pub struct Decoder<'a> {
+    bytes: &'a [u8],
+    mode: Mode,
+    address: usize,
+    position: usize,
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(bytes: &'a [u8], mode: Mode, address: usize) -> Self {
+        Self {
+            bytes,
+            mode,
+            address,
+            position: 0,
+        }
+    }
+
+    /// Get position of next instruction relative to start of bytes
+    pub fn get_position(&self) -> usize {
+        self.position
+    }
+}
+
+impl<'a> Iterator for Decoder<'a> {
+    type Item = (usize, Instruction);
+
+    /// Stops at end of bytes or at first instruction that fails to decode
+    fn next(&mut self) -> Option<Self::Item> {
+        let instruction = decode(&self.bytes[self.position..], self.mode).ok()?;
+        let address = self.address + self.position;
+        self.position += instruction.get_length();
+
+        Some((address, instruction))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode32(bytes: &[u8]) -> Instruction {
+        decode(bytes, Mode::Bits32).unwrap()
+    }
+
+    fn decode64(bytes: &[u8]) -> Instruction {
+        decode(bytes, Mode::Bits64).unwrap()
+    }
+
+    #[test]
+    fn lengths() {
+        let fixtures: &[(&[u8], usize)] = &[
+            // push ebp
+            (&[0x55], 1),
+            // mov ebp, esp
+            (&[0x8B, 0xEC], 2),
+            // sub esp, 0x10
+            (&[0x83, 0xEC, 0x10], 3),
+            // mov dword [eax], 0x11223344
+            (&[0xC7, 0x00, 0x44, 0x33, 0x22, 0x11], 6),
+            // mov eax, 0x12345678
+            (&[0xB8, 0x78, 0x56, 0x34, 0x12], 5),
+            // ret
+            (&[0xC3], 1),
+            // ret 8
+            (&[0xC2, 0x08, 0x00], 3),
+            // test eax, 0x1 (group 3, /0 has an immediate)
+            (&[0xF7, 0xC0, 0x01, 0x00, 0x00, 0x00], 6),
+            // neg eax (group 3, /3 has none)
+            (&[0xF7, 0xD8], 2),
+            // pshufb xmm0, xmm1
+            (&[0x66, 0x0F, 0x38, 0x00, 0xC1], 5),
+            // palignr xmm0, xmm1, 8
+            (&[0x66, 0x0F, 0x3A, 0x0F, 0xC1, 0x08], 6),
+        ];
+
+        for &(bytes, length) in fixtures {
+            assert_eq!(decode32(bytes).get_length(), length, "{:02X?}", bytes);
+        }
+    }
+
+    #[test]
+    fn modrm_sib_displacement() {
+        // mov eax, [esp + 8]
+        let instruction = decode32(&[0x8B, 0x44, 0x24, 0x08]);
+        assert_eq!(instruction.get_length(), 4);
+        assert_eq!(instruction.get_modrm(), Some(0x44));
+        assert_eq!(instruction.get_sib(), Some(0x24));
+        assert_eq!(instruction.get_displacement().unwrap().get_value(), 8);
+
+        // mov eax, [ebp - 4]
+        let displacement = decode32(&[0x8B, 0x45, 0xFC]).get_displacement().unwrap();
+        assert_eq!(displacement.get_offset(), 2);
+        assert_eq!(displacement.get_size(), 1);
+        assert_eq!(displacement.get_signed_value(), -4);
+
+        // mov eax, [0x12345678], absolute in 32-bit
+        let instruction = decode32(&[0x8B, 0x05, 0x78, 0x56, 0x34, 0x12]);
+        assert_eq!(instruction.get_length(), 6);
+        assert!(!instruction.is_rip_relative());
+        assert_eq!(
+            instruction.get_displacement().unwrap().get_value(),
+            0x12345678
+        );
+
+        // lea eax, [eax * 4 + 0x1000], SIB without base takes a disp32
+        let instruction = decode32(&[0x8D, 0x04, 0x85, 0x00, 0x10, 0x00, 0x00]);
+        assert_eq!(instruction.get_length(), 7);
+        assert_eq!(instruction.get_sib(), Some(0x85));
+        assert_eq!(instruction.get_displacement().unwrap().get_value(), 0x1000);
+
+        // mov eax, [ecx + edx * 2 + 0x12345678]
+        let instruction = decode32(&[0x8B, 0x84, 0x51, 0x78, 0x56, 0x34, 0x12]);
+        assert_eq!(instruction.get_length(), 7);
+        assert_eq!(instruction.get_displacement().unwrap().get_size(), 4);
+
+        // mov eax, ecx, register form has no displacement
+        assert_eq!(decode32(&[0x8B, 0xC1]).get_displacement(), None);
+
+        // mov ax, [bx + si + 0x10], 16-bit addressing has no SIB
+        let instruction = decode32(&[0x67, 0x8B, 0x40, 0x10]);
+        assert_eq!(instruction.get_length(), 4);
+        assert_eq!(instruction.get_sib(), None);
+    }
+
+    #[test]
+    fn prefixes() {
+        // mov ax, 0x1234
+        let instruction = decode32(&[0x66, 0xB8, 0x34, 0x12]);
+        assert_eq!(instruction.get_length(), 4);
+        assert_eq!(instruction.get_immediate().unwrap().get_size(), 2);
+
+        // mov word [eax], 0x1234
+        assert_eq!(decode32(&[0x66, 0xC7, 0x00, 0x34, 0x12]).get_length(), 5);
+
+        // rep movsd
+        assert_eq!(decode32(&[0xF3, 0xA5]).get_length(), 2);
+
+        // lock cmpxchg [edx], ecx
+        let instruction = decode32(&[0xF0, 0x0F, 0xB1, 0x0A]);
+        assert_eq!(instruction.get_length(), 4);
+        assert_eq!(instruction.get_map(), OpcodeMap::TwoByte);
+        assert_eq!(instruction.get_opcode(), 0xB1);
+
+        // mov eax, fs:[0x30]
+        let instruction = decode32(&[0x64, 0xA1, 0x30, 0x00, 0x00, 0x00]);
+        assert_eq!(instruction.get_length(), 6);
+        assert_eq!(instruction.get_displacement().unwrap().get_value(), 0x30);
+
+        // mov eax, [0x1234], address size override shortens the offset
+        assert_eq!(decode32(&[0x67, 0xA1, 0x34, 0x12]).get_length(), 4);
+
+        // mov rax, 0x1122334455667788, REX.W widens the immediate
+        let instruction = decode64(&[0x48, 0xB8, 0x88, 0x77, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11]);
+        assert_eq!(instruction.get_length(), 10);
+        assert_eq!(instruction.get_rex(), Some(0x48));
+        assert_eq!(
+            instruction.get_immediate().unwrap().get_value(),
+            0x1122334455667788
+        );
+
+        // REX followed by a legacy prefix doesn't count
+        assert_eq!(decode64(&[0x48, 0x66, 0xB8, 0x34, 0x12]).get_rex(), None);
+
+        // inc eax in 32-bit, not a REX prefix
+        assert_eq!(decode32(&[0x40]).get_length(), 1);
+    }
+
+    #[test]
+    fn relative_targets() {
+        // jmp $
+        let instruction = decode32(&[0xEB, 0xFE]);
+        assert_eq!(instruction.relative_target(0x1000), Some(0x1000));
+
+        // jz +0x10
+        let instruction = decode32(&[0x74, 0x10]);
+        assert_eq!(instruction.relative_target(0x1000), Some(0x1012));
+
+        // call +0x10
+        let instruction = decode32(&[0xE8, 0x10, 0x00, 0x00, 0x00]);
+        assert_eq!(instruction.get_length(), 5);
+        assert_eq!(instruction.relative_target(0x1000), Some(0x1015));
+
+        // jmp -0x100
+        let instruction = decode32(&[0xE9, 0x00, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(instruction.relative_target(0x1000), Some(0xF05));
+
+        // jz +0x20, two-byte map
+        let instruction = decode32(&[0x0F, 0x84, 0x20, 0x00, 0x00, 0x00]);
+        assert_eq!(instruction.get_length(), 6);
+        assert_eq!(instruction.relative_target(0x1000), Some(0x1026));
+
+        // jmp rel16, wraps within the low word
+        let instruction = decode32(&[0x66, 0xE9, 0x00, 0x10]);
+        assert_eq!(instruction.get_length(), 4);
+        assert_eq!(instruction.relative_target(0xF000), Some(0x0004));
+
+        // mov eax, ecx has no target
+        assert_eq!(decode32(&[0x8B, 0xC1]).relative_target(0x1000), None);
+    }
+
+    #[test]
+    fn rip_relative() {
+        // mov rax, [rip + 0x10]
+        let instruction = decode64(&[0x48, 0x8B, 0x05, 0x10, 0x00, 0x00, 0x00]);
+        assert_eq!(instruction.get_length(), 7);
+        assert!(instruction.is_rip_relative());
+        assert_eq!(instruction.rip_relative_target(0x1000), Some(0x1017));
+
+        // lea rax, [rip - 0x7]
+        let instruction = decode64(&[0x48, 0x8D, 0x05, 0xF9, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(instruction.rip_relative_target(0x1000), Some(0x1000));
+    }
+
+    #[test]
+    fn enter_has_two_immediates() {
+        let instruction = decode32(&[0xC8, 0x10, 0x00, 0x01]);
+        assert_eq!(instruction.get_length(), 4);
+        assert_eq!(instruction.get_immediate().unwrap().get_value(), 0x10);
+        assert_eq!(instruction.get_second_immediate().unwrap().get_value(), 1);
+        assert_eq!(instruction.operands().count(), 2);
+    }
+
+    #[test]
+    fn invalid() {
+        // Truncated call
+        assert!(decode(&[0xE8, 0x10, 0x00], Mode::Bits32).is_err());
+        // Truncated ModRM
+        assert!(decode(&[0x8B], Mode::Bits32).is_err());
+        // Nothing at all
+        assert!(decode(&[], Mode::Bits32).is_err());
+        // Over 15 bytes of prefixes
+        assert!(decode(&[0x66; 16], Mode::Bits32).is_err());
+        // push es isn't encodable in 64-bit
+        assert!(decode(&[0x06], Mode::Bits64).is_err());
+        assert!(decode(&[0x06], Mode::Bits32).is_ok());
+    }
+
+    #[test]
+    fn sweeps() {
+        // push ebp; mov ebp, esp; sub esp, 0x10
+        let bytes = [0x55, 0x8B, 0xEC, 0x83, 0xEC, 0x10];
+        assert_eq!(length_covering(&bytes, Mode::Bits32, 5).unwrap(), 6);
+        assert_eq!(length_covering(&bytes, Mode::Bits32, 1).unwrap(), 1);
+        assert!(length_covering(&bytes, Mode::Bits32, 7).is_err());
+
+        let addresses = Decoder::new(&bytes, Mode::Bits32, 0x1000)
+            .map(|(address, _)| address)
+            .collect::<Vec<_>>();
+        assert_eq!(addresses, [0x1000, 0x1001, 0x1003]);
+
+        // Stops at the first bad instruction
+        let mut decoder = Decoder::new(&[0x55, 0xE8], Mode::Bits32, 0);
+        assert!(decoder.next().is_some());
+        assert!(decoder.next().is_none());
+        assert_eq!(decoder.get_position(), 1);
+    }
+}
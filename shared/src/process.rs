@@ -0,0 +1,60 @@
+//! Process module
+
+use crate::{error::Error, GenericErrOr};
+use std::{io, mem::size_of};
+use winapi::{
+    shared::minwindef::DWORD,
+    um::{
+        handleapi::{CloseHandle, INVALID_HANDLE_VALUE},
+        tlhelp32::{
+            CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W,
+            TH32CS_SNAPPROCESS,
+        },
+    },
+};
+
+/// Type for process errors
+pub type ProcessErrOr<T> = GenericErrOr<T>;
+
+/// Find id of first running process whose executable is named `name`, case-insensitive
+///
+/// # Example
+///
+/// ```ignore
+/// let process = ProcessMemory::open(find_process_id("hl2.exe")?)?;
+/// ```
+pub fn find_process_id(name: &str) -> ProcessErrOr<DWORD> {
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+        if snapshot == INVALID_HANDLE_VALUE {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        let mut entry: PROCESSENTRY32W = std::mem::zeroed();
+        entry.dwSize = size_of::<PROCESSENTRY32W>() as _;
+
+        let mut result = Err(Error::CantFind.into());
+        let mut valid = Process32FirstW(snapshot, &mut entry);
+
+        while valid != 0 {
+            // Executable name is a null-terminated wide string
+            let length = entry
+                .szExeFile
+                .iter()
+                .position(|&x| x == 0)
+                .unwrap_or(entry.szExeFile.len());
+
+            if String::from_utf16_lossy(&entry.szExeFile[..length]).to_lowercase()
+                == name.to_lowercase()
+            {
+                result = Ok(entry.th32ProcessID);
+                break;
+            }
+
+            valid = Process32NextW(snapshot, &mut entry);
+        }
+
+        CloseHandle(snapshot);
+        result
+    }
+}
@@ -1,9 +1,28 @@
 //! Context module
 
 use crate::{error::Error, HMODULE};
-use game::types::{chl_client::CHLClient, convar::Cvar, mat_system_surface::MatSystemSurface};
-use shared::{memory::module::*, GenericErrOr};
-use std::sync::Mutex;
+use game::{
+    interfaces::find_interface,
+    signatures,
+    types::{
+        chl_client::{CHLClient, CHLClientIndices},
+        client_mode::ClientMode,
+        convar::Cvar,
+        mat_system_surface::MatSystemSurface,
+        panel::Panel,
+        vftable::VfTable,
+    },
+};
+use shared::{
+    error::Error as SharedError,
+    memory::{module::*, range::AddressRange},
+    GenericErrOr,
+};
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{Mutex, MutexGuard},
+    time::Duration,
+};
 
 /// Type for context errors
 pub(super) type ContextErrOr<T> = GenericErrOr<T>;
@@ -13,41 +32,98 @@ pub(super) struct Context {
     client: Module,
     engine: Module,
     vguimatsurface: Module,
+    vgui2: Module,
     cvar: Cvar,
     chl_client: CHLClient,
+    client_mode: ClientMode,
     mat_system_surface: MatSystemSurface,
+    panel: Panel,
 }
 
-/// Singleton and synchronizer for cheat operations
+// Module handle and game objects are process-wide, usable from any thread
+unsafe impl Send for Context {}
+
+/// How long to wait on each game module before giving up, for early injection
+const MODULE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How far into `CHLClient::HudProcessInput` we look for the client mode
+const HUD_PROCESS_INPUT_SIZE: usize = 0x20;
+
+/// Singleton and synchronizer for cheat operations, `None` outside of
+/// install and uninstall
 ///
 /// Don't use alone, that's why it's not public
-static mut CTX: Option<Mutex<Context>> = None;
+static CTX: Mutex<Option<Context>> = Mutex::new(None);
+
+/// Locked context, only handed out while there is one
+pub(super) struct ContextGuard(MutexGuard<'static, Option<Context>>);
+
+impl Deref for ContextGuard {
+    type Target = Context;
+
+    fn deref(&self) -> &Context {
+        self.0.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for ContextGuard {
+    fn deref_mut(&mut self) -> &mut Context {
+        self.0.as_mut().unwrap()
+    }
+}
 
 impl Context {
     fn new(module: HMODULE) -> ContextErrOr<Self> {
-        // Get modules
-        let client = Module::new("client.dll\0")?;
-        let engine = Module::new("engine.dll\0")?;
-        let vguimatsurface = Module::new("vguimatsurface.dll\0")?;
+        // Get modules, waiting for them in case we're injected early
+        let client = Module::wait_for("client.dll", MODULE_TIMEOUT)?;
+        let engine = Module::wait_for("engine.dll", MODULE_TIMEOUT)?;
+        let vguimatsurface = Module::wait_for("vguimatsurface.dll", MODULE_TIMEOUT)?;
+        let vgui2 = Module::wait_for("vgui2.dll", MODULE_TIMEOUT)?;
 
         // Get CCvar
-        let cvar = Cvar::from(engine.get_exports()["cvar"].deref(1)?.get_ptr());
+        let cvar = Cvar::from(engine.get_exports()["cvar"].path(&[0]).resolve()?.get_ptr());
 
         // Get CHLClient
         let chl_client = CHLClient::from(
             engine
-                .find_pattern("8B 0D ? ? ? ? 8B 15 ? ? ? ? 8B")?
+                .find_pattern(signatures::CHL_CLIENT)?
+                .offset(2)?
+                .path(&[0, 0])
+                .resolve()?
+                .get_ptr(),
+        );
+
+        // Get IClientMode, the global HudProcessInput forwards to
+        let hud_process_input = chl_client
+            .get_virtual_function(CHLClientIndices::HudProcessInput as isize)
+            .exposed_addr();
+        let client_mode = ClientMode::from(
+            client
+                .find_pattern_in(
+                    AddressRange::with_size(hud_process_input, HUD_PROCESS_INPUT_SIZE)?,
+                    signatures::CLIENT_MODE,
+                )?
                 .offset(2)?
-                .deref(2)?
+                .path(&[0, 0])
+                .resolve()?
                 .get_ptr(),
         );
 
         // Get MatSystemSurface
         let mat_system_surface = MatSystemSurface::from(
             vguimatsurface
-                .find_pattern("A3 ? ? ? ? 83 3D ? ? ? ? ? 75 14 8B 04 B7 6A 00 68")?
+                .find_pattern(signatures::MAT_SYSTEM_SURFACE)?
                 .offset(1)?
-                .deref(2)?
+                .path(&[0, 0])
+                .resolve()?
+                .get_ptr(),
+        );
+
+        // Get IPanel, a global the interface registry hands out
+        let panel = Panel::from(
+            find_interface(&vgui2, "VGUI_Panel")?
+                .get_instance()
+                .ok_or(SharedError::CantFind)?
                 .get_ptr(),
         );
 
@@ -56,9 +132,12 @@ impl Context {
             client,
             engine,
             vguimatsurface,
+            vgui2,
             cvar,
             chl_client,
+            client_mode,
             mat_system_surface,
+            panel,
         })
     }
 
@@ -80,6 +159,10 @@ impl Context {
         &self.vguimatsurface
     }
 
+    pub(super) fn get_vgui2(&self) -> &Module {
+        &self.vgui2
+    }
+
     // Interface getters
 
     pub(super) fn get_cvar(&self) -> &Cvar {
@@ -98,6 +181,14 @@ impl Context {
         &mut self.chl_client
     }
 
+    pub(super) fn get_client_mode(&self) -> &ClientMode {
+        &self.client_mode
+    }
+
+    pub(super) fn get_client_mode_mut(&mut self) -> &mut ClientMode {
+        &mut self.client_mode
+    }
+
     pub(super) fn get_mat_system_surface(&self) -> &MatSystemSurface {
         &self.mat_system_surface
     }
@@ -105,32 +196,48 @@ impl Context {
     pub(super) fn get_mat_system_surface_mut(&mut self) -> &mut MatSystemSurface {
         &mut self.mat_system_surface
     }
+
+    pub(super) fn get_panel(&self) -> &Panel {
+        &self.panel
+    }
+
+    pub(super) fn get_panel_mut(&mut self) -> &mut Panel {
+        &mut self.panel
+    }
 }
 
 // Singleton public utilities
 
 /// Install singleton
 pub(super) fn install(module: HMODULE) -> ContextErrOr<()> {
-    unsafe {
-        if CTX.is_some() {
-            return Err(Error::AlreadyInitialized.into());
-        } else {
-            CTX = Some(Mutex::from(Context::new(module)?));
-        };
+    let mut ctx = CTX.lock().unwrap();
+    if ctx.is_some() {
+        return Err(Error::AlreadyInitialized.into());
     }
 
+    *ctx = Some(Context::new(module)?);
+
     Ok(())
 }
 
-/// Get reference to promised (unchecked) context handle
-pub(super) fn get_ctx() -> &'static Mutex<Context> {
-    unsafe { CTX.as_ref().unwrap() }
+/// Drop singleton, waiting on whoever holds it first. Whoever asks for the
+/// context afterwards, late hook calls included, gets none
+pub(super) fn uninstall() {
+    *CTX.lock().unwrap() = None;
+}
+
+/// Get locked context, if it's installed and nobody else holds it
+pub(super) fn get_ctx() -> Option<ContextGuard> {
+    CTX.try_lock()
+        .ok()
+        .filter(|ctx| ctx.is_some())
+        .map(ContextGuard)
 }
 
 /// Syntactic sugar macro for attempting to acquire lock on context handle
 #[macro_export]
 macro_rules! try_acquire_ctx_locked {
     () => {
-        $crate::ctx::get_ctx().try_lock()
+        $crate::ctx::get_ctx()
     };
 }
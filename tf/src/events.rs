@@ -0,0 +1,366 @@
+//! Events module
+//!
+//! Hooks dispatch typed events here, features subscribe to the ones they care
+//! about instead of living in hook bodies.
+
+// Buses are what features build on, none ship in this crate, so subscribing
+// and unsubscribing are only reached from consumers and tests
+#![allow(dead_code)]
+
+use game::types::{chl_client::ClientFrameStage, user_cmd::UserCmd};
+use std::{
+    ffi::{c_float, c_int},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+};
+
+/// What a subscriber wants done after it ran
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(super) enum Flow {
+    /// Run the rest of the subscribers, then the original
+    Continue,
+    /// Run the rest of the subscribers, but not the original
+    SkipOriginal,
+}
+
+/// Handle to a subscription, to unsubscribe with
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub(super) struct SubscriptionId(usize);
+
+type Callback<E> = Arc<dyn Fn(&mut E) -> Flow + Send + Sync>;
+
+struct Subscriber<E> {
+    id: SubscriptionId,
+    name: &'static str,
+    priority: i32,
+    callback: Callback<E>,
+}
+
+// Derived one would want `E: Clone`
+impl<E> Clone for Subscriber<E> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            name: self.name,
+            priority: self.priority,
+            callback: self.callback.clone(),
+        }
+    }
+}
+
+type Subscribers<E> = Arc<Vec<Subscriber<E>>>;
+
+/// Subscribers of event `E`, run highest priority first, then in subscription order
+///
+/// Callbacks run on whichever game thread hit the hook, on a snapshot of the
+/// subscribers, so they may subscribe and unsubscribe, which takes effect on
+/// the next dispatch.
+///
+/// # Example
+///
+/// ```ignore
+/// events::FRAME_STAGE.subscribe("no_flash", 0, |event| {
+///     println!("Frame stage {:?}", event.stage);
+///     Flow::Continue
+/// });
+/// ```
+pub(super) struct EventBus<E> {
+    /// Replaced, never modified, on every change. None until first subscription
+    subscribers: RwLock<Option<Subscribers<E>>>,
+    next_id: AtomicUsize,
+}
+
+impl<E> EventBus<E> {
+    pub(super) const fn new() -> Self {
+        Self {
+            subscribers: RwLock::new(None),
+            next_id: AtomicUsize::new(0),
+        }
+    }
+
+    /// Get current subscribers, lock is only held while cloning the `Arc`
+    fn get_subscribers(&self) -> Option<Subscribers<E>> {
+        self.subscribers.read().unwrap().clone()
+    }
+
+    /// Replace subscribers with `f` applied to a copy of them
+    fn update<R>(&self, f: impl FnOnce(&mut Vec<Subscriber<E>>) -> R) -> R {
+        let mut subscribers = self.subscribers.write().unwrap();
+
+        let mut updated = subscribers.as_deref().cloned().unwrap_or_default();
+        let result = f(&mut updated);
+        *subscribers = Some(Arc::new(updated));
+
+        result
+    }
+
+    /// Run `callback` on every event, before subscribers of lower `priority`
+    pub(super) fn subscribe(
+        &self,
+        name: &'static str,
+        priority: i32,
+        callback: impl Fn(&mut E) -> Flow + Send + Sync + 'static,
+    ) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id.fetch_add(1, Ordering::Relaxed));
+
+        self.update(|subscribers| {
+            // After every subscriber of same or higher priority, keeps ties in order
+            let index = subscribers.partition_point(|subscriber| subscriber.priority >= priority);
+            subscribers.insert(
+                index,
+                Subscriber {
+                    id,
+                    name,
+                    priority,
+                    callback: Arc::new(callback),
+                },
+            );
+        });
+
+        id
+    }
+
+    /// Stop running subscription `id`, getting whether it existed
+    pub(super) fn unsubscribe(&self, id: SubscriptionId) -> bool {
+        self.update(|subscribers| {
+            let count = subscribers.len();
+            subscribers.retain(|subscriber| subscriber.id != id);
+
+            subscribers.len() != count
+        })
+    }
+
+    /// Get names of subscribers, in the order they run
+    pub(super) fn get_names(&self) -> Vec<&'static str> {
+        self.get_subscribers()
+            .map(|subscribers| {
+                subscribers
+                    .iter()
+                    .map(|subscriber| subscriber.name)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Run every subscriber on `event`, which they may modify, getting whether
+    /// the original should run
+    pub(super) fn dispatch(&self, event: &mut E) -> bool {
+        let Some(subscribers) = self.get_subscribers() else {
+            return true;
+        };
+
+        // Skipping the original doesn't skip the subscribers after it
+        let mut original = true;
+        for subscriber in subscribers.iter() {
+            if (subscriber.callback)(event) == Flow::SkipOriginal {
+                original = false;
+            }
+        }
+
+        original
+    }
+}
+
+/// `IBaseClientDLL::FrameStageNotify` is about to run
+#[derive(Debug, Clone, Copy)]
+pub(super) struct FrameStage {
+    pub(super) stage: ClientFrameStage,
+}
+
+impl EventBus<FrameStage> {
+    /// Run `callback` only on frame stage `stage`, before subscribers of lower
+    /// `priority`
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// events::FRAME_STAGE.subscribe_stage(
+    ///     ClientFrameStage::NetUpdatePostDataUpdateStart,
+    ///     "no_recoil",
+    ///     0,
+    ///     |_| Flow::Continue,
+    /// );
+    /// ```
+    pub(super) fn subscribe_stage(
+        &self,
+        stage: ClientFrameStage,
+        name: &'static str,
+        priority: i32,
+        callback: impl Fn(&mut FrameStage) -> Flow + Send + Sync + 'static,
+    ) -> SubscriptionId {
+        self.subscribe(name, priority, move |event| {
+            if event.stage == stage {
+                callback(event)
+            } else {
+                Flow::Continue
+            }
+        })
+    }
+}
+
+/// `IPanel::PaintTraverse` is about to run
+#[derive(Debug, Clone, Copy)]
+pub(super) struct PaintTraverse {
+    pub(super) panel: c_int,
+}
+
+/// Overlay panel was just painted, draw over the game here. Runs exactly once
+/// per frame
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Overlay {
+    pub(super) panel: c_int,
+}
+
+/// `IClientMode::CreateMove` is about to run, `cmd` is what gets sent to
+/// the server. Skipping the original keeps the engine from applying the
+/// command's view angles
+#[derive(Debug, Clone, Copy)]
+pub(super) struct CreateMove {
+    pub(super) input_sample_time: c_float,
+    pub(super) cmd: *mut UserCmd,
+}
+
+// Buses, one per event
+
+pub(super) static FRAME_STAGE: EventBus<FrameStage> = EventBus::new();
+pub(super) static PAINT_TRAVERSE: EventBus<PaintTraverse> = EventBus::new();
+pub(super) static OVERLAY: EventBus<Overlay> = EventBus::new();
+pub(super) static CREATE_MOVE: EventBus<CreateMove> = EventBus::new();
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Mutex, OnceLock};
+
+    #[derive(Debug, Default)]
+    struct Event {
+        value: i32,
+        seen: Vec<&'static str>,
+    }
+
+    /// Subscribe `name` to `bus`, noting it ran and getting `flow`
+    fn subscribe(
+        bus: &EventBus<Event>,
+        name: &'static str,
+        priority: i32,
+        flow: Flow,
+    ) -> SubscriptionId {
+        bus.subscribe(name, priority, move |event| {
+            event.seen.push(name);
+            flow
+        })
+    }
+
+    fn dispatch(bus: &EventBus<Event>) -> (bool, Vec<&'static str>) {
+        let mut event = Event::default();
+        let original = bus.dispatch(&mut event);
+
+        (original, event.seen)
+    }
+
+    #[test]
+    fn ordering() {
+        let bus = EventBus::new();
+        assert_eq!(dispatch(&bus), (true, vec![]));
+
+        subscribe(&bus, "first", 0, Flow::Continue);
+        subscribe(&bus, "low", -5, Flow::Continue);
+        subscribe(&bus, "high", 10, Flow::Continue);
+        subscribe(&bus, "second", 0, Flow::Continue);
+
+        // Highest priority first, ties in subscription order
+        let order = ["high", "first", "second", "low"];
+        assert_eq!(bus.get_names(), order);
+        assert_eq!(dispatch(&bus), (true, order.to_vec()));
+    }
+
+    #[test]
+    fn modification() {
+        let bus = EventBus::new();
+        bus.subscribe("double", 1, |event: &mut Event| {
+            event.value *= 2;
+            Flow::Continue
+        });
+        bus.subscribe("increment", 0, |event: &mut Event| {
+            event.value += 1;
+            Flow::Continue
+        });
+
+        // Later subscribers and the original see what earlier ones left
+        let mut event = Event {
+            value: 5,
+            ..Default::default()
+        };
+        assert!(bus.dispatch(&mut event));
+        assert_eq!(event.value, 11);
+    }
+
+    #[test]
+    fn skip_original() {
+        let bus = EventBus::new();
+        subscribe(&bus, "first", 1, Flow::Continue);
+        let skip = subscribe(&bus, "skip", 0, Flow::SkipOriginal);
+        subscribe(&bus, "last", -1, Flow::Continue);
+
+        // Whoever comes after skipping still runs
+        assert_eq!(dispatch(&bus), (false, vec!["first", "skip", "last"]));
+
+        assert!(bus.unsubscribe(skip));
+        assert!(!bus.unsubscribe(skip));
+        assert_eq!(dispatch(&bus), (true, vec!["first", "last"]));
+    }
+
+    #[test]
+    fn subscribe_stage() {
+        let bus = EventBus::new();
+        let count = Arc::new(AtomicUsize::new(0));
+
+        let counted = count.clone();
+        bus.subscribe_stage(ClientFrameStage::RenderStart, "render", 0, move |_| {
+            counted.fetch_add(1, Ordering::Relaxed);
+            Flow::SkipOriginal
+        });
+
+        let mut event = FrameStage {
+            stage: ClientFrameStage::RenderEnd,
+        };
+        assert!(bus.dispatch(&mut event));
+
+        event.stage = ClientFrameStage::RenderStart;
+        assert!(!bus.dispatch(&mut event));
+        assert_eq!(count.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn changes_during_dispatch() {
+        // Callbacks are `'static`, so they reach the bus through a static
+        static BUS: EventBus<Event> = EventBus::new();
+        static VICTIM: OnceLock<SubscriptionId> = OnceLock::new();
+        static SUBSCRIBED: Mutex<bool> = Mutex::new(false);
+
+        BUS.subscribe("remover", 1, |event| {
+            event.seen.push("remover");
+            if let Some(victim) = VICTIM.get() {
+                BUS.unsubscribe(*victim);
+            }
+
+            let mut subscribed = SUBSCRIBED.lock().unwrap();
+            if !*subscribed {
+                subscribe(&BUS, "late", -1, Flow::Continue);
+                *subscribed = true;
+            }
+
+            Flow::Continue
+        });
+        VICTIM
+            .set(subscribe(&BUS, "victim", 0, Flow::Continue))
+            .unwrap();
+
+        // Neither deadlocks, both take effect on the next dispatch
+        assert_eq!(dispatch(&BUS), (true, vec!["remover", "victim"]));
+        assert_eq!(dispatch(&BUS), (true, vec!["remover", "late"]));
+        assert_eq!(BUS.get_names(), ["remover", "late"]);
+    }
+}
@@ -0,0 +1,266 @@
+//! Hook module
+//!
+//! One hook type over every strategy we have, so hook bodies never care how
+//! they were installed. Bodies call the original through an `Original`, which
+//! every strategy fills in.
+
+use super::HooksErrOr;
+use detour::RawDetour;
+use game::types::{
+    shadow_vftable::ShadowVfTable,
+    vftable::{function_address, function_from_address, VfTable},
+    vftable_slot::VfTableSlot,
+};
+use shared::memory::generic_address::GenericAddress;
+use std::{
+    cell::Cell,
+    marker::PhantomData,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// How a hook gets between the game and the original function
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(super) enum HookStrategy {
+    /// Inline detour, patches the function's code, catches every caller
+    Detour,
+    /// Overwrites the entry in the real vftable, catches every instance
+    VfTableSlot,
+    /// Points the instance at a copy of its vftable, catches only this instance
+    ShadowVfTable,
+}
+
+/// Most distinct hooks a thread can be nested inside of and still have
+/// reentry detected
+const MAX_NESTED_HOOKS: usize = 8;
+
+thread_local! {
+    /// Hooks this thread is inside of, by their original's address, 0 being free.
+    /// Has no destructor, so game threads outliving an unload never call into us
+    static ACTIVE: Cell<[usize; MAX_NESTED_HOOKS]> = const { Cell::new([0; MAX_NESTED_HOOKS]) };
+}
+
+/// Original function a hook body calls through, `F` being its function pointer type
+pub(super) struct Original<F> {
+    address: AtomicUsize,
+    /// Calls currently inside the hook body, reentrant ones included
+    in_flight: AtomicUsize,
+    phantom_data: PhantomData<F>,
+}
+
+impl<F: Copy> Original<F> {
+    pub(super) const fn new() -> Self {
+        Self {
+            address: AtomicUsize::new(0),
+            in_flight: AtomicUsize::new(0),
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Count a call as inside the hook body until the returned guard drops,
+    /// first thing every hook body does
+    pub(super) fn enter(&'static self) -> Call {
+        let key = self as *const Self as usize;
+        self.in_flight.fetch_add(1, Ordering::AcqRel);
+
+        let (reentrant, tracked) = ACTIVE.with(|active| {
+            let mut keys = active.get();
+            if keys.contains(&key) {
+                return (true, false);
+            }
+
+            // Nested too deep, reentry into this hook goes unnoticed
+            let Some(free) = keys.iter().position(|&key| key == 0) else {
+                return (false, false);
+            };
+
+            keys[free] = key;
+            active.set(keys);
+
+            (false, true)
+        });
+
+        Call {
+            key,
+            in_flight: &self.in_flight,
+            reentrant,
+            tracked,
+        }
+    }
+
+    /// Get original function, only call once its hook is installed
+    pub(super) fn get(&self) -> F {
+        let address = self.address.load(Ordering::Acquire);
+        assert_ne!(address, 0, "original called before its hook was installed");
+
+        // Set from the function hooked, or its trampoline
+        unsafe { function_from_address(address) }
+    }
+
+    fn set(&self, address: usize) {
+        self.address.store(address, Ordering::Release);
+    }
+}
+
+// Only ever holds a function pointer
+unsafe impl<F> Sync for Original<F> {}
+
+/// Call inside a hook body, counted as in flight while alive
+pub(super) struct Call {
+    key: usize,
+    in_flight: &'static AtomicUsize,
+    reentrant: bool,
+    /// Whether we took a slot in `ACTIVE`
+    tracked: bool,
+}
+
+impl Call {
+    /// Whether this thread was already inside the same hook, in which case the
+    /// body should only call through to the original
+    pub(super) fn is_reentrant(&self) -> bool {
+        self.reentrant
+    }
+}
+
+impl Drop for Call {
+    fn drop(&mut self) {
+        if self.tracked {
+            ACTIVE.with(|active| {
+                let mut keys = active.get();
+                keys.iter_mut()
+                    .filter(|key| **key == self.key)
+                    .for_each(|key| *key = 0);
+                active.set(keys);
+            });
+        }
+
+        self.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Strategy specific state
+enum Target {
+    Detour(RawDetour),
+    VfTableSlot(VfTableSlot<usize>),
+    ShadowVfTable(ShadowVfTable),
+}
+
+/// Hook on a virtual function, installed disabled
+pub(super) struct Hook {
+    name: &'static str,
+    index: isize,
+    function: usize,
+    target: Target,
+    enabled: bool,
+    /// Counter of calls inside our function, shared with its `Original`
+    in_flight: &'static AtomicUsize,
+}
+
+impl Hook {
+    /// Hook `index` of `object`'s vftable with `function`, using `strategy`.
+    /// `original` is filled in right away, so `function` can call through it
+    /// as soon as the hook is enabled
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// static FRAME_STAGE_NOTIFY: Original<FrameStageNotify_t> = Original::new();
+    ///
+    /// let mut hook = Hook::new(
+    ///     "FrameStageNotify",
+    ///     HookStrategy::ShadowVfTable,
+    ///     ctx.get_chl_client(),
+    ///     CHLClientIndices::FrameStageNotify as isize,
+    ///     frame_stage_notify as FrameStageNotify_t,
+    ///     &FRAME_STAGE_NOTIFY,
+    /// )?;
+    /// hook.enable()?;
+    /// ```
+    pub(super) fn new<F: Copy>(
+        name: &'static str,
+        strategy: HookStrategy,
+        object: &impl VfTable,
+        index: isize,
+        function: F,
+        original: &'static Original<F>,
+    ) -> HooksErrOr<Self> {
+        let function = function_address(function);
+
+        let (target, address) = match strategy {
+            HookStrategy::Detour => {
+                let detour = unsafe {
+                    RawDetour::new(
+                        object.get_virtual_function(index).get_ptr(),
+                        function as *const (),
+                    )?
+                };
+                let trampoline = detour.trampoline() as *const () as usize;
+
+                (Target::Detour(detour), trampoline)
+            }
+            HookStrategy::VfTableSlot => {
+                let slot = VfTableSlot::<usize>::new(object, index)?;
+                let address = slot.get_original_address().exposed_addr();
+
+                (Target::VfTableSlot(slot), address)
+            }
+            HookStrategy::ShadowVfTable => {
+                let shadow = ShadowVfTable::new(object)?;
+                let address = shadow.get_original(index as usize)?.exposed_addr();
+
+                (Target::ShadowVfTable(shadow), address)
+            }
+        };
+
+        original.set(address);
+
+        Ok(Self {
+            name,
+            index,
+            function,
+            target,
+            enabled: false,
+            in_flight: &original.in_flight,
+        })
+    }
+
+    /// Get name hook was installed under
+    pub(super) fn get_name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Get number of calls currently inside our function
+    pub(super) fn get_in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Acquire)
+    }
+
+    /// Whether calls are currently redirected to our function
+    pub(super) fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Start redirecting calls to our function
+    pub(super) fn enable(&mut self) -> HooksErrOr<()> {
+        match &mut self.target {
+            Target::Detour(detour) => unsafe { detour.enable()? },
+            Target::VfTableSlot(slot) => slot.swap(self.function)?,
+            Target::ShadowVfTable(shadow) => {
+                shadow.hook(self.index as usize, GenericAddress::from(self.function))?;
+            }
+        }
+
+        self.enabled = true;
+        Ok(())
+    }
+
+    /// Stop redirecting calls, original function is called directly again
+    pub(super) fn disable(&mut self) -> HooksErrOr<()> {
+        match &mut self.target {
+            Target::Detour(detour) => unsafe { detour.disable()? },
+            Target::VfTableSlot(slot) => slot.restore()?,
+            Target::ShadowVfTable(shadow) => shadow.unhook(self.index as usize)?,
+        }
+
+        self.enabled = false;
+        Ok(())
+    }
+}
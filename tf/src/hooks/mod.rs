@@ -2,61 +2,221 @@
 
 #![allow(non_camel_case_types)]
 
-use crate::{ctx::Context, try_acquire_ctx_locked};
-use detour::static_detour;
+mod hook;
+mod registry;
+use crate::{ctx::Context, events, try_acquire_ctx_locked};
 use game::types::{
-    chl_client::CHLClientIndices, mat_system_surface::MatSystemSurfaceIndices, vftable::VfTable,
+    chl_client::{CHLClientIndices, ClientFrameStage},
+    client_mode::ClientModeIndices,
+    mat_system_surface::MatSystemSurfaceIndices,
+    panel::VPANEL,
+    user_cmd::UserCmd,
+    vftable::VfTable,
+};
+use hook::{Hook, HookStrategy, Original};
+use registry::HookRegistry;
+use shared::{thiscall, GenericErrOr};
+use std::{
+    ffi::{c_float, c_int},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
 };
-use shared::GenericErrOr;
-use std::ffi::c_int;
 
 /// Type for hooks errors
 pub(super) type HooksErrOr<T> = GenericErrOr<T>;
 
-static_detour! {
-    // `static_detour` doesn't support type aliases
-    // so we have to redefine the types for transmutations
-    static FrameStageNotifyHook: unsafe extern "stdcall" fn(c_int) -> c_int;
-    static PaintTraverseHook: unsafe extern "thiscall" fn(*const usize, c_int) -> c_int;
+thiscall! {
+    type FrameStageNotify_t = unsafe fn(*const usize, c_int) -> c_int;
+    type PaintTraverse_t = unsafe fn(*const usize, c_int) -> c_int;
+    type CreateMove_t = unsafe fn(*const usize, c_float, *mut UserCmd) -> bool;
 }
 
-// Above
-type FrameStageNotify_t = unsafe extern "stdcall" fn(c_int) -> c_int;
-type PaintTraverse_t = unsafe extern "thiscall" fn(*const usize, c_int) -> c_int;
+// Originals, filled in by whichever strategy installs the hook
+static FRAME_STAGE_NOTIFY: Original<FrameStageNotify_t> = Original::new();
+static PAINT_TRAVERSE: Original<PaintTraverse_t> = Original::new();
+static CREATE_MOVE: Original<CreateMove_t> = Original::new();
+
+/// Panel overlay is drawn on, 0 until we've found it
+static OVERLAY_PANEL: AtomicUsize = AtomicUsize::new(0);
+
+/// How long to wait on calls already inside a hook before giving up on freeing it
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Extra wait once drained, for threads between a detour's jump and `enter`,
+/// or between dropping their call and returning, which aren't counted
+const DRAIN_GRACE: Duration = Duration::from_millis(100);
+
+/// Installed hooks, kept alive until we uninstall them
+static REGISTRY: Mutex<HookRegistry> = Mutex::new(HookRegistry::new());
+
+thiscall! {
+    unsafe fn frame_stage_notify(this: *const usize, stage: c_int) -> c_int {
+        let call = FRAME_STAGE_NOTIFY.enter();
+        if call.is_reentrant() {
+            return FRAME_STAGE_NOTIFY.get()(this, stage);
+        }
+
+        let mut event = events::FrameStage {
+            stage: ClientFrameStage::from(stage),
+        };
+        if !events::FRAME_STAGE.dispatch(&mut event) {
+            return 0;
+        }
+
+        FRAME_STAGE_NOTIFY.get()(this, event.stage.into())
+    }
+
+    unsafe fn paint_traverse(this: *const usize, panel: c_int) -> c_int {
+        let call = PAINT_TRAVERSE.enter();
+        if call.is_reentrant() {
+            return PAINT_TRAVERSE.get()(this, panel);
+        }
 
-fn frame_stage_notify(stage: c_int) -> c_int {
-    unsafe { FrameStageNotifyHook.call(stage) }
+        let mut event = events::PaintTraverse { panel };
+        let result = if events::PAINT_TRAVERSE.dispatch(&mut event) {
+            PAINT_TRAVERSE.get()(this, event.panel)
+        } else {
+            0
+        };
+
+        // Drawn after the panel itself, so it's on top, even if its painting was skipped
+        if is_overlay_panel(panel) {
+            events::OVERLAY.dispatch(&mut events::Overlay { panel });
+        }
+
+        result
+    }
+
+    unsafe fn create_move(
+        this: *const usize,
+        input_sample_time: c_float,
+        cmd: *mut UserCmd,
+    ) -> bool {
+        let call = CREATE_MOVE.enter();
+        if call.is_reentrant() {
+            return CREATE_MOVE.get()(this, input_sample_time, cmd);
+        }
+
+        let mut event = events::CreateMove {
+            input_sample_time,
+            cmd,
+        };
+        if !events::CREATE_MOVE.dispatch(&mut event) {
+            return false;
+        }
+
+        CREATE_MOVE.get()(this, event.input_sample_time, event.cmd)
+    }
 }
 
-fn paint_traverse(this: *const usize, panel: c_int) -> c_int {
-    unsafe { PaintTraverseHook.call(this, panel) }
+/// Whether `panel` is the one overlay is drawn on. First top-level overlay
+/// panel we see becomes it, so it's only ever one panel, painted once per frame
+fn is_overlay_panel(panel: c_int) -> bool {
+    match OVERLAY_PANEL.load(Ordering::Acquire) {
+        0 => {
+            // Busy context only delays finding it to the next call
+            let Some(ctx) = try_acquire_ctx_locked!() else {
+                return false;
+            };
+
+            if !ctx.get_panel().is_overlay(panel as VPANEL) {
+                return false;
+            }
+
+            OVERLAY_PANEL.store(panel as usize, Ordering::Release);
+            true
+        }
+        overlay => overlay == panel as usize,
+    }
 }
 
 pub(super) fn install(ctx: &Context) -> HooksErrOr<()> {
-    // Apply hooks
-    unsafe {
-        FrameStageNotifyHook
-            .initialize(
-                ctx.get_chl_client()
-                    .get_virtual_function(CHLClientIndices::FrameStageNotify as isize)
-                    .transmute::<FrameStageNotify_t>()?,
-                frame_stage_notify,
-            )?
-            .enable()?;
-
+    // Strategy is picked per hook, bodies above don't change with it
+    let hooks = [
+        Hook::new(
+            "FrameStageNotify",
+            HookStrategy::Detour,
+            ctx.get_chl_client(),
+            CHLClientIndices::FrameStageNotify as isize,
+            frame_stage_notify as FrameStageNotify_t,
+            &FRAME_STAGE_NOTIFY,
+        )?,
         // You should be able to get to SolvePanels by looking for words such as 'Panel' or
         // 'Paint' in vguimatsystemsurface, find said function, breakpoint it's client.dll
         // version, breakpoint it, go to first entry in vguimatsurface in stackframe,
         // then PaintTraverse is one function after.
-        PaintTraverseHook
-            .initialize(
-                ctx.get_mat_system_surface()
-                    .get_virtual_function(MatSystemSurfaceIndices::PaintTraverse as isize)
-                    .transmute::<PaintTraverse_t>()?,
-                paint_traverse,
-            )?
-            .enable()?;
+        Hook::new(
+            "PaintTraverse",
+            HookStrategy::Detour,
+            ctx.get_mat_system_surface(),
+            MatSystemSurfaceIndices::PaintTraverse as isize,
+            paint_traverse as PaintTraverse_t,
+            &PAINT_TRAVERSE,
+        )?,
+        Hook::new(
+            "CreateMove",
+            HookStrategy::VfTableSlot,
+            ctx.get_client_mode(),
+            ClientModeIndices::CreateMove as isize,
+            create_move as CreateMove_t,
+            &CREATE_MOVE,
+        )?,
+    ];
+
+    let mut registry = REGISTRY.lock().unwrap();
+    for hook in hooks {
+        registry.add(hook)?;
     }
 
-    Ok(())
+    // Apply hooks
+    registry.enable_all()
+}
+
+/// Get names of installed hooks, in install order
+pub(super) fn get_names() -> Vec<&'static str> {
+    REGISTRY.lock().unwrap().get_names()
+}
+
+/// Whether hook named `name` is installed and enabled
+pub(super) fn is_enabled(name: &str) -> bool {
+    REGISTRY
+        .lock()
+        .unwrap()
+        .get(name)
+        .is_some_and(Hook::is_enabled)
+}
+
+/// Enable hook named `name`
+pub(super) fn enable(name: &str) -> HooksErrOr<()> {
+    REGISTRY.lock().unwrap().enable(name)
+}
+
+/// Disable hook named `name`, the original is called directly again
+pub(super) fn disable(name: &str) -> HooksErrOr<()> {
+    REGISTRY.lock().unwrap().disable(name)
+}
+
+/// Flip hook named `name`, getting whether it's now enabled
+pub(super) fn toggle(name: &str) -> HooksErrOr<bool> {
+    REGISTRY.lock().unwrap().toggle(name)
+}
+
+/// Disable and drop every hook, latest first, so we can be unloaded
+/// and injected again
+///
+/// Fails with `Timeout` if calls are still inside hooks after disabling, in
+/// which case hooks are kept, disabled, as freeing them would crash those calls.
+pub(super) fn uninstall() -> HooksErrOr<()> {
+    let mut registry = REGISTRY.lock().unwrap();
+    let result = registry.disable_all();
+
+    // Calls that got in before we disabled may still be running on trampolines
+    registry.wait_for_calls(DRAIN_TIMEOUT)?;
+    thread::sleep(DRAIN_GRACE);
+
+    result.and(registry.clear())
 }
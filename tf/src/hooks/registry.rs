@@ -0,0 +1,143 @@
+//! Hook registry module
+//!
+//! Every installed hook, by name, so they can be switched at runtime and torn
+//! down in order when we unload.
+
+use super::{hook::Hook, HooksErrOr};
+use crate::error::Error;
+use shared::error::Error as SharedError;
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How often in flight calls are checked while draining
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Installed hooks, in install order
+#[derive(Default)]
+pub(super) struct HookRegistry {
+    hooks: Vec<Hook>,
+}
+
+impl HookRegistry {
+    pub(super) const fn new() -> Self {
+        Self { hooks: Vec::new() }
+    }
+
+    /// Get names of installed hooks, in install order
+    pub(super) fn get_names(&self) -> Vec<&'static str> {
+        self.hooks.iter().map(Hook::get_name).collect()
+    }
+
+    /// Get hook named `name`
+    pub(super) fn get(&self, name: &str) -> Option<&Hook> {
+        self.hooks.iter().find(|hook| hook.get_name() == name)
+    }
+
+    /// Get hook named `name` mutably
+    fn get_mut(&mut self, name: &str) -> HooksErrOr<&mut Hook> {
+        Ok(self
+            .hooks
+            .iter_mut()
+            .find(|hook| hook.get_name() == name)
+            .ok_or(SharedError::CantFind)?)
+    }
+
+    /// Take ownership of `hook`, names are unique
+    pub(super) fn add(&mut self, hook: Hook) -> HooksErrOr<()> {
+        if self.get(hook.get_name()).is_some() {
+            return Err(Error::AlreadyInitialized.into());
+        }
+
+        self.hooks.push(hook);
+        Ok(())
+    }
+
+    /// Enable hook named `name`, does nothing if it already is
+    pub(super) fn enable(&mut self, name: &str) -> HooksErrOr<()> {
+        let hook = self.get_mut(name)?;
+        if !hook.is_enabled() {
+            hook.enable()?;
+        }
+
+        Ok(())
+    }
+
+    /// Disable hook named `name`, does nothing if it already is
+    pub(super) fn disable(&mut self, name: &str) -> HooksErrOr<()> {
+        let hook = self.get_mut(name)?;
+        if hook.is_enabled() {
+            hook.disable()?;
+        }
+
+        Ok(())
+    }
+
+    /// Flip hook named `name`, getting whether it's now enabled
+    pub(super) fn toggle(&mut self, name: &str) -> HooksErrOr<bool> {
+        let hook = self.get_mut(name)?;
+        if hook.is_enabled() {
+            hook.disable()?;
+        } else {
+            hook.enable()?;
+        }
+
+        Ok(hook.is_enabled())
+    }
+
+    /// Enable every hook, in install order, stopping at the first failure
+    pub(super) fn enable_all(&mut self) -> HooksErrOr<()> {
+        for hook in self.hooks.iter_mut().filter(|hook| !hook.is_enabled()) {
+            hook.enable()?;
+        }
+
+        Ok(())
+    }
+
+    /// Disable every hook, latest first, reporting the first failure
+    pub(super) fn disable_all(&mut self) -> HooksErrOr<()> {
+        let mut result = Ok(());
+
+        for hook in self.hooks.iter_mut().rev().filter(|hook| hook.is_enabled()) {
+            if let Err(error) = hook.disable() {
+                if result.is_ok() {
+                    result = Err(error);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Wait until no call is inside any hook, giving up after `timeout`
+    ///
+    /// Only meaningful once hooks are disabled, otherwise new calls keep coming.
+    pub(super) fn wait_for_calls(&self, timeout: Duration) -> HooksErrOr<()> {
+        let start = Instant::now();
+
+        while self.hooks.iter().any(|hook| hook.get_in_flight() != 0) {
+            if start.elapsed() >= timeout {
+                return Err(SharedError::Timeout.into());
+            }
+
+            thread::sleep(DRAIN_POLL_INTERVAL);
+        }
+
+        Ok(())
+    }
+
+    /// Disable and drop every hook, latest first, reporting the first failure
+    ///
+    /// Hooks that failed to disable are dropped anyway, their strategy restores
+    /// what it can on drop.
+    pub(super) fn clear(&mut self) -> HooksErrOr<()> {
+        let result = self.disable_all();
+
+        while let Some(hook) = self.hooks.pop() {
+            drop(hook);
+        }
+
+        result
+    }
+}
@@ -6,9 +6,30 @@ compile_error!("Windows support only");
 
 pub mod ctx;
 pub mod error;
+pub mod events;
 pub mod hooks;
 use shared::{console::*, entry_point, GenericErrOr, *};
-use winapi::um::winnt::DLL_PROCESS_ATTACH;
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+use winapi::um::{
+    libloaderapi::FreeLibraryAndExitThread,
+    winuser::{GetAsyncKeyState, VK_END},
+};
+
+/// Key that ejects us from the game
+const EJECT_KEY: i32 = VK_END;
+
+/// How often the eject key is polled
+const EJECT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long initialization may take before we report it as hung
+const INITIALIZATION_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Whether we've already cleaned up, eject and detach both try to
+static UNINITIALIZED: AtomicBool = AtomicBool::new(false);
 
 /// Handles initializing console (global context), sets title to what's given
 fn initialize_console(title: &str) -> ConsoleErrOr<()> {
@@ -41,7 +62,7 @@ fn initialize_context(module: HMODULE) -> GenericErrOr<()> {
 /// Handles initialization of hooks
 fn initialize_hooks() -> GenericErrOr<()> {
     // Get context
-    if let Ok(ctx) = try_acquire_ctx_locked!() {
+    if let Some(ctx) = try_acquire_ctx_locked!() {
         // Install hooks
         hooks::install(&*ctx)?;
     }
@@ -52,18 +73,101 @@ fn initialize_hooks() -> GenericErrOr<()> {
     Ok(())
 }
 
-entry_point!(|module, reason_for_call| {
-    if reason_for_call != DLL_PROCESS_ATTACH {
-        return false;
+/// Handles initialization of everything, off the loader lock
+fn initialize(module: HMODULE) -> GenericErrOr<()> {
+    initialize_console("tf\0")?;
+    initialize_context(module)?;
+    initialize_hooks()?;
+
+    if let Some(ctx) = try_acquire_ctx_locked!() {
+        ctx.get_cvar().console_print(c_str!("Hello, world!\0"));
     }
 
-    initialize_console("tf\0").expect("Failed initializing console");
-    initialize_context(module).expect("Failed initializing context");
-    initialize_hooks().expect("Failed initializing hooks");
+    spawn_eject_watcher(module);
 
-    if let Ok(ctx) = try_acquire_ctx_locked!() {
-        ctx.get_cvar().console_print(c_str!("Hello, world!\0"));
+    Ok(())
+}
+
+/// Undoes what initialization did to the game, leaving the console. Fails if
+/// calls are stuck inside hooks, as nothing they use can go then
+fn release() -> bool {
+    // Hooks go first, they're what reaches into the context
+    if let Err(error) = hooks::uninstall() {
+        println!("Failed uninstalling hooks: {}", error);
+
+        // Hooks we couldn't drain are kept, and so is everything else
+        if !hooks::get_names().is_empty() {
+            return false;
+        }
+    }
+
+    ctx::uninstall();
+
+    true
+}
+
+/// Undoes everything initialization did, latest first, once. Fails, to be
+/// tried again, if it's not safe to unload yet
+fn uninitialize() -> bool {
+    if UNINITIALIZED.swap(true, Ordering::AcqRel) {
+        return true;
     }
 
+    if !release() {
+        UNINITIALIZED.store(false, Ordering::Release);
+        return false;
+    }
+
+    // Notify user, console goes right after
+    println!("Uninitialized");
+
+    let _ = free_console();
+
     true
-});
+}
+
+/// Undoes what failed initialization got to, keeping the console up so its
+/// error can be read until the user ejects
+fn initialization_failed(module: HMODULE) {
+    let _ = release();
+
+    println!("Press End to unload");
+    spawn_eject_watcher(module);
+}
+
+/// Waits for eject key on its own thread, then cleans up and unloads us
+fn spawn_eject_watcher(module: HMODULE) {
+    // Module handles aren't `Send`, the address is all we need
+    let module = module as usize;
+
+    thread::spawn(move || {
+        loop {
+            while unsafe { GetAsyncKeyState(EJECT_KEY) } as u16 & 0x8000 == 0 {
+                thread::sleep(EJECT_POLL_INTERVAL);
+            }
+
+            if uninitialize() {
+                break;
+            }
+
+            println!("Calls are still inside hooks, press End to try again");
+        }
+
+        // Never returns, our code is gone once it does its job
+        unsafe { FreeLibraryAndExitThread(module as HMODULE, 0) };
+    });
+}
+
+// Context already waits on each game module, this only catches a hang
+entry_point!(
+    thread initialize,
+    Some(INITIALIZATION_TIMEOUT),
+    initialization_failed,
+    // Unloaded by someone else, or the game's closing. Nothing to clean up in
+    // the latter, threads we'd drain are gone and the memory goes with us
+    |_, reserved: LPVOID| {
+        if reserved.is_null() {
+            uninitialize();
+        }
+    }
+);